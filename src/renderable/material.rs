@@ -0,0 +1,129 @@
+//! Surface materials
+//!
+//! A material describes how an object's surface looks independently of its
+//! geometry. Materials are sent to the GPU per instance, so restyling an object
+//! never touches its cached vertex buffers.
+
+use crate::renderer::shader::ShaderId;
+use crate::renderer::texture::TextureId;
+
+/// How an object's surface is shaded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    /// Linear RGB color the surface reflects
+    pub base_color: [f32; 3],
+    /// 1 is fully opaque; lower values are blended over the objects behind, drawn after every opaque object
+    pub opacity: f32,
+    /// Light emitted by the surface itself, added after lighting
+    pub emissive: [f32; 3],
+    /// 0 gives small, sharp specular highlights, 1 removes them entirely
+    pub roughness: f32,
+    /// Multiply the base color by the mesh's vertex colors
+    pub use_vertex_colors: bool,
+    /// Texture multiplied into the base color, sampled with the mesh's UVs
+    pub texture: Option<TextureId>,
+    /// Shader the object is drawn with, see [`crate::renderer::shader`] for the contract
+    pub shader: ShaderId,
+    /// Block the directional light from reaching other objects, when it has shadows enabled
+    pub casts_shadows: bool,
+    /// Darken the surface where other objects block the directional light
+    pub receives_shadows: bool,
+}
+
+impl Default for Material {
+    /// White base multiplied by the vertex colors, so meshes keep their built-in coloring
+    fn default() -> Self {
+        Self {
+            base_color: [1.0, 1.0, 1.0],
+            opacity: 1.0,
+            emissive: [0.0, 0.0, 0.0],
+            roughness: 0.5,
+            use_vertex_colors: true,
+            texture: None,
+            shader: ShaderId::BASIC,
+            casts_shadows: true,
+            receives_shadows: true,
+        }
+    }
+}
+
+impl Material {
+    /// Solid-colored material that ignores the mesh's vertex colors
+    pub fn new(base_color: [f32; 3]) -> Self {
+        Self {
+            base_color,
+            use_vertex_colors: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_emissive(mut self, emissive: [f32; 3]) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_vertex_colors(mut self, use_vertex_colors: bool) -> Self {
+        self.use_vertex_colors = use_vertex_colors;
+        self
+    }
+
+    pub fn with_texture(mut self, texture: TextureId) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    pub fn with_shader(mut self, shader: ShaderId) -> Self {
+        self.shader = shader;
+        self
+    }
+
+    pub fn with_cast_shadows(mut self, casts_shadows: bool) -> Self {
+        self.casts_shadows = casts_shadows;
+        self
+    }
+
+    pub fn with_receive_shadows(mut self, receives_shadows: bool) -> Self {
+        self.receives_shadows = receives_shadows;
+        self
+    }
+
+    /// Whether the surface lets objects behind it show through, and so is drawn in the sorted transparent pass
+    pub fn is_transparent(&self) -> bool {
+        self.opacity < 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_builders() {
+        let material = Material::new([0.2, 0.4, 0.6])
+            .with_opacity(1.5)
+            .with_emissive([0.1, 0.0, 0.0])
+            .with_roughness(-1.0)
+            .with_cast_shadows(false);
+
+        assert_eq!(material.base_color, [0.2, 0.4, 0.6]);
+        assert_eq!(material.opacity, 1.0);
+        assert_eq!(material.emissive, [0.1, 0.0, 0.0]);
+        assert_eq!(material.roughness, 0.0);
+        assert!(!material.use_vertex_colors);
+        assert!(Material::default().use_vertex_colors);
+        assert!(!material.casts_shadows);
+        assert!(material.receives_shadows);
+        assert!(!material.is_transparent());
+        assert!(material.with_opacity(0.5).is_transparent());
+    }
+}
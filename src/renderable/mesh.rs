@@ -3,7 +3,9 @@
 //! This module defines the Mesh trait for 3D geometry and provides
 //! factory functions for creating common primitive shapes.
 
-use super::{Vertex, VertexProvider};
+use super::{Indices, Vertex, VertexProvider};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 /// Trait for 3D mesh geometry
 ///
@@ -17,14 +19,9 @@ pub trait Mesh: VertexProvider {
     /// Returns (min, max) corners of axis-aligned bounding box
     fn bounds(&self) -> (glam::Vec3, glam::Vec3);
 
-    /// Check if this mesh uses indexed rendering (for future optimization)
+    /// Check if this mesh uses indexed rendering
     fn is_indexed(&self) -> bool {
-        false
-    }
-
-    /// Get index data if this mesh uses indexed rendering
-    fn index_data(&self) -> Option<&[u16]> {
-        None
+        self.indices().is_some()
     }
 }
 
@@ -47,16 +44,22 @@ impl TriangleMesh {
             Vertex {
                 position: [0.0, height * (2.0 / 3.0), 0.0],
                 color: [1.0, 0.0, 0.0],
+                normal: [0.0, 0.0, 1.0],
+                uv: [0.5, 0.0],
             },
             // Bottom left vertex (green)
             Vertex {
                 position: [-half_base, -height * (1.0 / 3.0), 0.0],
                 color: [0.0, 1.0, 0.0],
+                normal: [0.0, 0.0, 1.0],
+                uv: [0.0, 1.0],
             },
             // Bottom right vertex (blue)
             Vertex {
                 position: [half_base, -height * (1.0 / 3.0), 0.0],
                 color: [0.0, 0.0, 1.0],
+                normal: [0.0, 0.0, 1.0],
+                uv: [1.0, 1.0],
             },
         ];
 
@@ -76,6 +79,10 @@ impl VertexProvider for TriangleMesh {
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(&self.vertices)
     }
+
+    fn geometry_key(&self) -> u64 {
+        parameter_key(self.mesh_type(), &[self.scale.to_bits()])
+    }
 }
 
 impl Mesh for TriangleMesh {
@@ -96,7 +103,8 @@ impl Mesh for TriangleMesh {
 /// Quad mesh (two triangles forming a rectangle)
 #[derive(Debug, Clone)]
 pub struct QuadMesh {
-    vertices: [Vertex; 6], // Two triangles = 6 vertices
+    vertices: [Vertex; 4], // Four corners shared by two triangles
+    indices: Indices,
     width: f32,
     height: f32,
 }
@@ -108,36 +116,39 @@ impl QuadMesh {
         let half_height = height / 2.0;
 
         let vertices = [
-            // First triangle (top-left, bottom-left, top-right)
             Vertex {
                 position: [-half_width, half_height, 0.0],
-                color: [1.0, 0.0, 0.0], // Red
+                color: [1.0, 0.0, 0.0], // 0: top-left, red
+                normal: [0.0, 0.0, 1.0],
+                uv: [0.0, 0.0],
             },
             Vertex {
                 position: [-half_width, -half_height, 0.0],
-                color: [0.0, 1.0, 0.0], // Green
-            },
-            Vertex {
-                position: [half_width, half_height, 0.0],
-                color: [0.0, 0.0, 1.0], // Blue
+                color: [0.0, 1.0, 0.0], // 1: bottom-left, green
+                normal: [0.0, 0.0, 1.0],
+                uv: [0.0, 1.0],
             },
-            // Second triangle (top-right, bottom-left, bottom-right)
             Vertex {
                 position: [half_width, half_height, 0.0],
-                color: [0.0, 0.0, 1.0], // Blue
-            },
-            Vertex {
-                position: [-half_width, -half_height, 0.0],
-                color: [0.0, 1.0, 0.0], // Green
+                color: [0.0, 0.0, 1.0], // 2: top-right, blue
+                normal: [0.0, 0.0, 1.0],
+                uv: [1.0, 0.0],
             },
             Vertex {
                 position: [half_width, -half_height, 0.0],
-                color: [1.0, 1.0, 0.0], // Yellow
+                color: [1.0, 1.0, 0.0], // 3: bottom-right, yellow
+                normal: [0.0, 0.0, 1.0],
+                uv: [1.0, 1.0],
             },
         ];
 
+        // First triangle (top-left, bottom-left, top-right)
+        // Second triangle (top-right, bottom-left, bottom-right)
+        let indices = Indices::new(vec![0, 1, 2, 2, 1, 3], vertices.len());
+
         Self {
             vertices,
+            indices,
             width,
             height,
         }
@@ -150,12 +161,20 @@ impl VertexProvider for QuadMesh {
     }
 
     fn vertex_count(&self) -> usize {
-        6
+        4
+    }
+
+    fn indices(&self) -> Option<&Indices> {
+        Some(&self.indices)
     }
 
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(&self.vertices)
     }
+
+    fn geometry_key(&self) -> u64 {
+        parameter_key(self.mesh_type(), &[self.width.to_bits(), self.height.to_bits()])
+    }
 }
 
 impl Mesh for QuadMesh {
@@ -176,7 +195,8 @@ impl Mesh for QuadMesh {
 /// Cube mesh (12 triangles forming a cube)
 #[derive(Debug, Clone)]
 pub struct CubeMesh {
-    vertices: [Vertex; 36], // 6 faces * 2 triangles * 3 vertices = 36
+    vertices: [Vertex; 24], // 6 faces * 4 corners, so each face keeps its own color
+    indices: Indices,       // 6 faces * 2 triangles * 3 indices = 36
     size: f32,
 }
 
@@ -195,12 +215,22 @@ impl CubeMesh {
             [0.0, 1.0, 1.0], // Left face - Cyan
         ];
 
+        // Outward normal of each face, in the same order as the colors
+        let normals = [
+            [0.0, 0.0, 1.0],  // Front
+            [0.0, 0.0, -1.0], // Back
+            [0.0, 1.0, 0.0],  // Top
+            [0.0, -1.0, 0.0], // Bottom
+            [1.0, 0.0, 0.0],  // Right
+            [-1.0, 0.0, 0.0], // Left
+        ];
+
         let mut vertices = [Vertex {
             position: [0.0, 0.0, 0.0],
             color: [0.0, 0.0, 0.0],
-        }; 36];
-
-        let mut _vertex_index = 0;
+            normal: [0.0, 0.0, 0.0],
+            uv: [0.0, 0.0],
+        }; 24];
 
         // Define cube vertices systematically
         // Using standard cube vertex positions
@@ -217,39 +247,47 @@ impl CubeMesh {
             [-half_size, half_size, -half_size],  // 7: back top left
         ];
 
-        // Define faces with proper counter-clockwise winding when viewed from outside
-        let face_indices = [
-            // Front face (0, 1, 2, 3) - looking at +Z
-            [0, 1, 2, 0, 2, 3], // CCW: bottom-left, bottom-right, top-right, then bottom-left, top-right, top-left
-            // Back face (5, 4, 7, 6) - looking at -Z (reversed order for CCW)
-            [5, 4, 7, 5, 7, 6], // CCW when viewed from outside
-            // Top face (3, 2, 6, 7) - looking down at +Y
-            [3, 2, 6, 3, 6, 7], // CCW when viewed from above
-            // Bottom face (4, 5, 1, 0) - looking up at -Y (reversed order for CCW)
-            [4, 5, 1, 4, 1, 0], // CCW when viewed from below
-            // Right face (1, 5, 6, 2) - looking at +X
-            [1, 5, 6, 1, 6, 2], // CCW when viewed from outside
-            // Left face (4, 0, 3, 7) - looking at -X (reversed order for CCW)
-            [4, 0, 3, 4, 3, 7], // CCW when viewed from outside
+        // Define face corners in counter-clockwise order when viewed from outside
+        let face_corners = [
+            // Front face - looking at +Z
+            [0, 1, 2, 3], // CCW: bottom-left, bottom-right, top-right, top-left
+            // Back face - looking at -Z (reversed order for CCW)
+            [5, 4, 7, 6], // CCW when viewed from outside
+            // Top face - looking down at +Y
+            [3, 2, 6, 7], // CCW when viewed from above
+            // Bottom face - looking up at -Y (reversed order for CCW)
+            [4, 5, 1, 0], // CCW when viewed from below
+            // Right face - looking at +X
+            [1, 5, 6, 2], // CCW when viewed from outside
+            // Left face - looking at -X (reversed order for CCW)
+            [4, 0, 3, 7], // CCW when viewed from outside
         ];
 
-        let mut vertex_index = 0;
+        // Every face shows the whole texture upright, in the same corner order as face_corners
+        let face_uvs = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+
+        let mut indices = Vec::with_capacity(36);
 
-        // Generate vertices for each face
-        for (face_idx, indices) in face_indices.iter().enumerate() {
+        // Generate 4 vertices and 2 triangles for each face
+        for (face_idx, corners) in face_corners.iter().enumerate() {
             let color = colors[face_idx];
+            let base = (face_idx * 4) as u32;
 
-            // Each face has 6 vertices (2 triangles)
-            for &vertex_idx in indices.iter() {
-                vertices[vertex_index] = Vertex {
+            for (corner_idx, &vertex_idx) in corners.iter().enumerate() {
+                vertices[face_idx * 4 + corner_idx] = Vertex {
                     position: vertices_pos[vertex_idx],
                     color,
+                    normal: normals[face_idx],
+                    uv: face_uvs[corner_idx],
                 };
-                vertex_index += 1;
             }
+
+            indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
         }
 
-        Self { vertices, size }
+        let indices = Indices::new(indices, vertices.len());
+
+        Self { vertices, indices, size }
     }
 }
 
@@ -259,12 +297,20 @@ impl VertexProvider for CubeMesh {
     }
 
     fn vertex_count(&self) -> usize {
-        36
+        24
+    }
+
+    fn indices(&self) -> Option<&Indices> {
+        Some(&self.indices)
     }
 
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(&self.vertices)
     }
+
+    fn geometry_key(&self) -> u64 {
+        parameter_key(self.mesh_type(), &[self.size.to_bits()])
+    }
 }
 
 impl Mesh for CubeMesh {
@@ -285,6 +331,7 @@ impl Mesh for CubeMesh {
 #[derive(Debug, Clone)]
 pub struct CircleMesh {
     vertices: Vec<Vertex>,
+    indices: Indices,
     radius: f32,
     segments: u32,
 }
@@ -293,43 +340,40 @@ impl CircleMesh {
     /// Create a new circle mesh with the given radius and number of segments
     pub fn new(radius: f32, segments: u32) -> Self {
         let segments = segments.max(3); // Minimum 3 segments for a triangle
-        let mut vertices = Vec::with_capacity((segments * 3) as usize);
+        let mut vertices = Vec::with_capacity((segments + 1) as usize);
+        let mut indices = Vec::with_capacity((segments * 3) as usize);
         
         // Center vertex (white)
-        let center = Vertex {
+        vertices.push(Vertex {
             position: [0.0, 0.0, 0.0],
             color: [1.0, 1.0, 1.0],
-        };
+            normal: [0.0, 0.0, 1.0],
+            uv: [0.5, 0.5],
+        });
         
-        // Create triangular segments
+        // Rim vertices; color varies around the circle (hue wheel effect)
+        // UVs map the circle onto the texture's inscribed circle
         for i in 0..segments {
-            let angle1 = (i as f32) * 2.0 * std::f32::consts::PI / (segments as f32);
-            let angle2 = ((i + 1) as f32) * 2.0 * std::f32::consts::PI / (segments as f32);
-            
-            // Color varies around the circle (hue wheel effect)
-            let hue1 = angle1 / (2.0 * std::f32::consts::PI);
-            let hue2 = angle2 / (2.0 * std::f32::consts::PI);
-            
-            let color1 = hsv_to_rgb(hue1, 0.8, 1.0);
-            let color2 = hsv_to_rgb(hue2, 0.8, 1.0);
-            
-            let vertex1 = Vertex {
-                position: [radius * angle1.cos(), radius * angle1.sin(), 0.0],
-                color: color1,
-            };
-            
-            let vertex2 = Vertex {
-                position: [radius * angle2.cos(), radius * angle2.sin(), 0.0],
-                color: color2,
-            };
+            let angle = (i as f32) * 2.0 * std::f32::consts::PI / (segments as f32);
+            let hue = angle / (2.0 * std::f32::consts::PI);
             
-            // Add triangle: center -> vertex1 -> vertex2
-            vertices.push(center);
-            vertices.push(vertex1);
-            vertices.push(vertex2);
+            vertices.push(Vertex {
+                position: [radius * angle.cos(), radius * angle.sin(), 0.0],
+                color: hsv_to_rgb(hue, 0.8, 1.0),
+                normal: [0.0, 0.0, 1.0],
+                uv: [0.5 + 0.5 * angle.cos(), 0.5 - 0.5 * angle.sin()],
+            });
+        }
+        
+        // Create triangular segments: center -> rim i -> rim i + 1
+        for i in 0..segments {
+            let next_i = (i + 1) % segments;
+            indices.extend_from_slice(&[0, 1 + i, 1 + next_i]);
         }
         
-        Self { vertices, radius, segments }
+        let indices = Indices::new(indices, vertices.len());
+        
+        Self { vertices, indices, radius, segments }
     }
 
     /// Get the radius of the circle
@@ -351,10 +395,18 @@ impl VertexProvider for CircleMesh {
     fn vertex_count(&self) -> usize {
         self.vertices.len()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        Some(&self.indices)
+    }
     
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(&self.vertices)
     }
+
+    fn geometry_key(&self) -> u64 {
+        parameter_key(self.mesh_type(), &[self.radius.to_bits(), self.segments])
+    }
 }
 
 impl Mesh for CircleMesh {
@@ -374,6 +426,7 @@ impl Mesh for CircleMesh {
 #[derive(Debug, Clone)]
 pub struct CylinderMesh {
     vertices: Vec<Vertex>,
+    indices: Indices,
     radius: f32,
     height: f32,
     segments: u32,
@@ -387,73 +440,113 @@ impl CylinderMesh {
         
         let half_height = height / 2.0;
         
-        // Generate vertices for top and bottom circles
-        let mut top_verts = Vec::new();
-        let mut bottom_verts = Vec::new();
+        // Generate the rings of the sides and caps. The caps get their own copy of each
+        // ring so side vertices can have radial normals while cap vertices face up or down.
+        // The side rings repeat their first vertex at the end so the texture can wrap
+        // all the way around without the last segment squeezing it back to the start.
+        let mut side_top = Vec::new();
+        let mut side_bottom = Vec::new();
+        let mut cap_top = Vec::new();
+        let mut cap_bottom = Vec::new();
         
-        for i in 0..segments {
+        for i in 0..=segments {
             let angle = (i as f32) * 2.0 * std::f32::consts::PI / (segments as f32);
             let x = radius * angle.cos();
             let z = radius * angle.sin();
+            let radial = [angle.cos(), 0.0, angle.sin()];
             
             // Color varies by height and angle
-            let hue = angle / (2.0 * std::f32::consts::PI);
+            let hue = (i % segments) as f32 / segments as f32;
             let top_color = hsv_to_rgb(hue, 0.6, 1.0);
             let bottom_color = hsv_to_rgb(hue, 0.6, 0.7);
             
-            top_verts.push(Vertex {
+            // Cylindrical UVs: u wraps around the side left to right as seen from outside
+            let u = 1.0 - i as f32 / segments as f32;
+            side_top.push(Vertex {
                 position: [x, half_height, z],
                 color: top_color,
+                normal: radial,
+                uv: [u, 0.0],
             });
+            side_bottom.push(Vertex {
+                position: [x, -half_height, z],
+                color: bottom_color,
+                normal: radial,
+                uv: [u, 1.0],
+            });
+            
+            if i == segments {
+                continue;
+            }
             
-            bottom_verts.push(Vertex {
+            // Planar UVs for the caps
+            cap_top.push(Vertex {
+                position: [x, half_height, z],
+                color: top_color,
+                normal: [0.0, 1.0, 0.0],
+                uv: [0.5 + 0.5 * angle.cos(), 0.5 + 0.5 * angle.sin()],
+            });
+            cap_bottom.push(Vertex {
                 position: [x, -half_height, z],
                 color: bottom_color,
+                normal: [0.0, -1.0, 0.0],
+                uv: [0.5 + 0.5 * angle.cos(), 0.5 - 0.5 * angle.sin()],
             });
         }
         
+        // Layout: side top ring, side bottom ring, cap top ring, cap bottom ring, top center, bottom center
+        let ring = segments + 1;
+        let top = |i: u32| i;
+        let bottom = |i: u32| ring + i;
+        let cap_top_ring = |i: u32| 2 * ring + i;
+        let cap_bottom_ring = |i: u32| 2 * ring + segments + i;
+        let top_center = 2 * ring + 2 * segments;
+        let bottom_center = top_center + 1;
+        
+        vertices.extend(side_top);
+        vertices.extend(side_bottom);
+        vertices.extend(cap_top);
+        vertices.extend(cap_bottom);
+        vertices.push(Vertex {
+            position: [0.0, half_height, 0.0],
+            color: [1.0, 0.8, 0.8], // Light red
+            normal: [0.0, 1.0, 0.0],
+            uv: [0.5, 0.5],
+        });
+        vertices.push(Vertex {
+            position: [0.0, -half_height, 0.0],
+            color: [0.8, 0.8, 1.0], // Light blue
+            normal: [0.0, -1.0, 0.0],
+            uv: [0.5, 0.5],
+        });
+        
+        let mut indices = Vec::with_capacity((segments * 12) as usize);
+        
         // Create side faces (quads made of two triangles each)
+        // Increasing angles run right to left when seen from outside
         for i in 0..segments {
-            let next_i = (i + 1) % segments;
-            
             // First triangle of quad (counter-clockwise from outside)
-            vertices.push(bottom_verts[i as usize]);
-            vertices.push(top_verts[next_i as usize]);
-            vertices.push(top_verts[i as usize]);
+            indices.extend_from_slice(&[bottom(i), top(i), top(i + 1)]);
             
             // Second triangle of quad (counter-clockwise from outside)
-            vertices.push(bottom_verts[i as usize]);
-            vertices.push(bottom_verts[next_i as usize]);
-            vertices.push(top_verts[next_i as usize]);
+            indices.extend_from_slice(&[bottom(i), top(i + 1), bottom(i + 1)]);
         }
         
-        // Create top cap (triangular fan)
-        let top_center = Vertex {
-            position: [0.0, half_height, 0.0],
-            color: [1.0, 0.8, 0.8], // Light red
-        };
-        
+        // Create top cap (triangular fan), winding for upward normal
         for i in 0..segments {
             let next_i = (i + 1) % segments;
-            vertices.push(top_center);
-            vertices.push(top_verts[i as usize]); // Correct winding for upward normal
-            vertices.push(top_verts[next_i as usize]);
+            indices.extend_from_slice(&[top_center, cap_top_ring(next_i), cap_top_ring(i)]);
         }
         
-        // Create bottom cap (triangular fan)
-        let bottom_center = Vertex {
-            position: [0.0, -half_height, 0.0],
-            color: [0.8, 0.8, 1.0], // Light blue
-        };
-        
+        // Create bottom cap (triangular fan), reverse winding for downward normal
         for i in 0..segments {
             let next_i = (i + 1) % segments;
-            vertices.push(bottom_center);
-            vertices.push(bottom_verts[next_i as usize]); // Reverse winding for downward normal
-            vertices.push(bottom_verts[i as usize]);
+            indices.extend_from_slice(&[bottom_center, cap_bottom_ring(i), cap_bottom_ring(next_i)]);
         }
         
-        Self { vertices, radius, height, segments }
+        let indices = Indices::new(indices, vertices.len());
+        
+        Self { vertices, indices, radius, height, segments }
     }
 
     /// Get the radius of the cylinder
@@ -480,10 +573,18 @@ impl VertexProvider for CylinderMesh {
     fn vertex_count(&self) -> usize {
         self.vertices.len()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        Some(&self.indices)
+    }
     
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(&self.vertices)
     }
+
+    fn geometry_key(&self) -> u64 {
+        parameter_key(self.mesh_type(), &[self.radius.to_bits(), self.height.to_bits(), self.segments])
+    }
 }
 
 impl Mesh for CylinderMesh {
@@ -504,6 +605,7 @@ impl Mesh for CylinderMesh {
 #[derive(Debug, Clone)]
 pub struct ConeMesh {
     vertices: Vec<Vertex>,
+    indices: Indices,
     radius: f32,
     height: f32,
     segments: u32,
@@ -517,51 +619,90 @@ impl ConeMesh {
         
         let half_height = height / 2.0;
         
-        // Apex vertex (top of cone)
-        let apex = Vertex {
-            position: [0.0, half_height, 0.0],
-            color: [1.0, 1.0, 0.0], // Yellow
+        // Outward normal of the slanted side at a given angle around the axis
+        let side_normal = |angle: f32| {
+            glam::Vec3::new(height * angle.cos(), radius, height * angle.sin())
+                .normalize_or_zero()
+                .to_array()
         };
         
-        // Generate base circle vertices
-        let mut base_verts = Vec::new();
-        for i in 0..segments {
+        // Side vertices: one apex per segment, so each can take the normal halfway
+        // across its segment, plus the base ring with slanted normals. The side ring
+        // repeats its first vertex so the texture can wrap all the way around.
+        // The base gets its own ring facing straight down.
+        let mut apexes = Vec::new();
+        let mut side_ring = Vec::new();
+        let mut base_ring = Vec::new();
+        for i in 0..=segments {
             let angle = (i as f32) * 2.0 * std::f32::consts::PI / (segments as f32);
+            let mid_angle = (i as f32 + 0.5) * 2.0 * std::f32::consts::PI / (segments as f32);
             let x = radius * angle.cos();
             let z = radius * angle.sin();
             
-            let hue = angle / (2.0 * std::f32::consts::PI);
+            let hue = (i % segments) as f32 / segments as f32;
             let color = hsv_to_rgb(hue, 0.8, 0.9);
             
-            base_verts.push(Vertex {
+            // Cylindrical UVs: u wraps around the side left to right as seen from outside
+            let u = 1.0 - i as f32 / segments as f32;
+            side_ring.push(Vertex {
+                position: [x, -half_height, z],
+                color,
+                normal: side_normal(angle),
+                uv: [u, 1.0],
+            });
+            
+            if i == segments {
+                continue;
+            }
+            
+            // Apex vertex (top of cone)
+            apexes.push(Vertex {
+                position: [0.0, half_height, 0.0],
+                color: [1.0, 1.0, 0.0], // Yellow
+                normal: side_normal(mid_angle),
+                uv: [u - 0.5 / segments as f32, 0.0],
+            });
+            base_ring.push(Vertex {
                 position: [x, -half_height, z],
                 color,
+                normal: [0.0, -1.0, 0.0],
+                uv: [0.5 + 0.5 * angle.cos(), 0.5 - 0.5 * angle.sin()],
             });
         }
         
+        // Layout: apexes, side ring, base ring, base center
+        let apex = |i: u32| i;
+        let side = |i: u32| segments + i;
+        let base = |i: u32| 2 * segments + 1 + i;
+        let base_center = 3 * segments + 1;
+        
+        vertices.extend(apexes);
+        vertices.extend(side_ring);
+        vertices.extend(base_ring);
+        vertices.push(Vertex {
+            position: [0.0, -half_height, 0.0],
+            color: [0.8, 0.8, 0.8], // Gray
+            normal: [0.0, -1.0, 0.0],
+            uv: [0.5, 0.5],
+        });
+        
+        let mut indices = Vec::with_capacity((segments * 6) as usize);
+        
         // Create side faces (triangles from apex to base edge)
+        // Increasing angles run right to left when seen from outside
         for i in 0..segments {
-            let next_i = (i + 1) % segments;
-            
-            vertices.push(apex);
-            vertices.push(base_verts[i as usize]); // Correct winding for outward-facing triangles
-            vertices.push(base_verts[next_i as usize]);
+            indices.extend_from_slice(&[apex(i), side(i + 1), side(i)]); // Counter-clockwise from outside
         }
         
         // Create base (triangular fan) - winding for downward-facing surface
-        let base_center = Vertex {
-            position: [0.0, -half_height, 0.0],
-            color: [0.8, 0.8, 0.8], // Gray
-        };
-        
         for i in 0..segments {
             let next_i = (i + 1) % segments;
-            vertices.push(base_center);
-            vertices.push(base_verts[next_i as usize]); // Reversed winding for downward face
-            vertices.push(base_verts[i as usize]);
+            indices.extend_from_slice(&[base_center, base(i), base(next_i)]); // Counter-clockwise from below
         }
         
-        Self { vertices, radius, height, segments }
+        let indices = Indices::new(indices, vertices.len());
+        
+        Self { vertices, indices, radius, height, segments }
     }
 
     /// Get the radius of the cone base
@@ -588,10 +729,18 @@ impl VertexProvider for ConeMesh {
     fn vertex_count(&self) -> usize {
         self.vertices.len()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        Some(&self.indices)
+    }
     
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(&self.vertices)
     }
+
+    fn geometry_key(&self) -> u64 {
+        parameter_key(self.mesh_type(), &[self.radius.to_bits(), self.height.to_bits(), self.segments])
+    }
 }
 
 impl Mesh for ConeMesh {
@@ -612,6 +761,7 @@ impl Mesh for ConeMesh {
 #[derive(Debug, Clone)]
 pub struct SphereMesh {
     vertices: Vec<Vertex>,
+    indices: Indices,
     radius: f32,
     latitude_segments: u32,
     longitude_segments: u32,
@@ -622,10 +772,9 @@ impl SphereMesh {
     pub fn new(radius: f32, latitude_segments: u32, longitude_segments: u32) -> Self {
         let lat_segs = latitude_segments.max(3);
         let lon_segs = longitude_segments.max(3);
-        let mut vertices = Vec::new();
-        
         // Generate sphere vertices using spherical coordinates
-        let mut sphere_verts = Vec::new();
+        let mut vertices = Vec::with_capacity(((lat_segs + 1) * (lon_segs + 1)) as usize);
+        let mut indices = Vec::with_capacity((lat_segs * lon_segs * 6) as usize);
         
         for lat in 0..=lat_segs {
             let theta = (lat as f32) * std::f32::consts::PI / (lat_segs as f32);
@@ -648,9 +797,12 @@ impl SphereMesh {
                     (sin_theta * sin_phi + 1.0) * 0.5,
                 ];
                 
-                sphere_verts.push(Vertex {
+                vertices.push(Vertex {
                     position: [x, y, z],
                     color,
+                    normal: [sin_theta * cos_phi, cos_theta, sin_theta * sin_phi],
+                    // Equirectangular UVs, with u increasing eastward as seen from outside
+                    uv: [1.0 - lon as f32 / lon_segs as f32, lat as f32 / lat_segs as f32],
                 });
             }
         }
@@ -666,19 +818,17 @@ impl SphereMesh {
                 
                 // First triangle: bottom-left -> bottom-right -> top-left
                 // This creates counter-clockwise winding when viewed from outside
-                vertices.push(sphere_verts[i0 as usize]);
-                vertices.push(sphere_verts[i1 as usize]);
-                vertices.push(sphere_verts[i2 as usize]);
+                indices.extend_from_slice(&[i0, i1, i2]);
                 
                 // Second triangle: bottom-right -> top-right -> top-left
                 // This also creates counter-clockwise winding when viewed from outside
-                vertices.push(sphere_verts[i1 as usize]);
-                vertices.push(sphere_verts[i3 as usize]);
-                vertices.push(sphere_verts[i2 as usize]);
+                indices.extend_from_slice(&[i1, i3, i2]);
             }
         }
         
-        Self { vertices, radius, latitude_segments: lat_segs, longitude_segments: lon_segs }
+        let indices = Indices::new(indices, vertices.len());
+        
+        Self { vertices, indices, radius, latitude_segments: lat_segs, longitude_segments: lon_segs }
     }
 
     /// Get the radius of the sphere
@@ -705,10 +855,18 @@ impl VertexProvider for SphereMesh {
     fn vertex_count(&self) -> usize {
         self.vertices.len()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        Some(&self.indices)
+    }
     
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(&self.vertices)
     }
+
+    fn geometry_key(&self) -> u64 {
+        parameter_key(self.mesh_type(), &[self.radius.to_bits(), self.latitude_segments, self.longitude_segments])
+    }
 }
 
 impl Mesh for SphereMesh {
@@ -724,6 +882,17 @@ impl Mesh for SphereMesh {
     }
 }
 
+/// Geometry key for meshes generated entirely from a few parameters
+///
+/// Hashing the construction parameters is much cheaper than hashing the vertex data
+/// every frame, and yields the same key for identical meshes.
+fn parameter_key(mesh_type: &str, params: &[u32]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    mesh_type.hash(&mut hasher);
+    params.hash(&mut hasher);
+    hasher.finish()
+}
+
 // Helper function to convert HSV to RGB
 fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
     let c = v * s;
@@ -6,14 +6,17 @@
 use glam;
 use web_time::Instant;
 use crate::renderer::config::CullingMode;
+use crate::scene::EntityId;
 
+pub mod material;
 pub mod mesh;
 mod transforms;
 pub mod vertex;
 
+pub use material::Material;
 pub use mesh::{CubeMesh, Mesh, QuadMesh, TriangleMesh, CircleMesh, CylinderMesh, ConeMesh, SphereMesh};
 pub use transforms::Transform;
-pub use vertex::{Vertex, VertexProvider};
+pub use vertex::{Indices, Vertex, VertexProvider};
 
 /// Quad object (rectangle made of two triangles)
 #[derive(Debug, Clone)]
@@ -22,6 +25,8 @@ pub struct Quad {
     transform: Transform,
     is_dirty: bool,
     culling_mode: CullingMode,
+    material: Material,
+    entity_id: Option<EntityId>,
 }
 
 impl Quad {
@@ -32,6 +37,8 @@ impl Quad {
             transform: Transform::new(),
             is_dirty: true,
             culling_mode: CullingMode::None, // 2D objects default to no culling
+            material: Material::default(),
+            entity_id: None,
         }
     }
 
@@ -54,6 +61,21 @@ impl Quad {
     pub fn set_culling_mode(&mut self, mode: CullingMode) {
         self.culling_mode = mode;
     }
+
+    /// Get the surface material
+    pub fn get_material(&self) -> Material {
+        self.material
+    }
+
+    /// Set the surface material; the mesh and its cached buffers are unaffected
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Set the id reported when this object is picked; scenes set it when the object is added
+    pub fn set_entity_id(&mut self, entity_id: Option<EntityId>) {
+        self.entity_id = entity_id;
+    }
 }
 
 /// Cube object (3D cube made of 12 triangles)
@@ -63,6 +85,8 @@ pub struct Cube {
     transform: Transform,
     is_dirty: bool,
     culling_mode: CullingMode,
+    material: Material,
+    entity_id: Option<EntityId>,
 }
 
 impl Cube {
@@ -73,6 +97,8 @@ impl Cube {
             transform: Transform::new(),
             is_dirty: true,
             culling_mode: CullingMode::BackfaceCulling, // 3D objects benefit from backface culling
+            material: Material::default(),
+            entity_id: None,
         }
     }
 
@@ -95,6 +121,21 @@ impl Cube {
     pub fn set_culling_mode(&mut self, mode: CullingMode) {
         self.culling_mode = mode;
     }
+
+    /// Get the surface material
+    pub fn get_material(&self) -> Material {
+        self.material
+    }
+
+    /// Set the surface material; the mesh and its cached buffers are unaffected
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Set the id reported when this object is picked; scenes set it when the object is added
+    pub fn set_entity_id(&mut self, entity_id: Option<EntityId>) {
+        self.entity_id = entity_id;
+    }
 }
 
 // Implement Renderable for Quad
@@ -131,6 +172,18 @@ impl Renderable for Quad {
     fn get_culling_mode(&self) -> CullingMode {
         self.culling_mode
     }
+
+    fn get_material(&self) -> Material {
+        self.material
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        self.mesh.bounds()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.entity_id
+    }
 }
 
 // Implement VertexProvider for Quad
@@ -138,6 +191,14 @@ impl VertexProvider for Quad {
     fn vertices(&self) -> &[Vertex] {
         self.mesh.vertices()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        self.mesh.indices()
+    }
+
+    fn geometry_key(&self) -> u64 {
+        self.mesh.geometry_key()
+    }
 }
 
 // Implement Renderable for Cube
@@ -174,6 +235,18 @@ impl Renderable for Cube {
     fn get_culling_mode(&self) -> CullingMode {
         self.culling_mode
     }
+
+    fn get_material(&self) -> Material {
+        self.material
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        self.mesh.bounds()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.entity_id
+    }
 }
 
 // Implement VertexProvider for Cube
@@ -181,6 +254,14 @@ impl VertexProvider for Cube {
     fn vertices(&self) -> &[Vertex] {
         self.mesh.vertices()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        self.mesh.indices()
+    }
+
+    fn geometry_key(&self) -> u64 {
+        self.mesh.geometry_key()
+    }
 }
 
 /// Circle object (2D circle made of triangular segments)
@@ -190,6 +271,8 @@ pub struct Circle {
     transform: Transform,
     is_dirty: bool,
     culling_mode: CullingMode,
+    material: Material,
+    entity_id: Option<EntityId>,
     start_time: Instant,
 }
 
@@ -201,6 +284,8 @@ impl Circle {
             transform: Transform::new(),
             is_dirty: true,
             culling_mode: CullingMode::None, // 2D objects default to no culling
+            material: Material::default(),
+            entity_id: None,
             start_time: Instant::now(),
         }
     }
@@ -224,6 +309,21 @@ impl Circle {
     pub fn set_culling_mode(&mut self, mode: CullingMode) {
         self.culling_mode = mode;
     }
+
+    /// Get the surface material
+    pub fn get_material(&self) -> Material {
+        self.material
+    }
+
+    /// Set the surface material; the mesh and its cached buffers are unaffected
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Set the id reported when this object is picked; scenes set it when the object is added
+    pub fn set_entity_id(&mut self, entity_id: Option<EntityId>) {
+        self.entity_id = entity_id;
+    }
 }
 
 impl Renderable for Circle {
@@ -265,12 +365,32 @@ impl Renderable for Circle {
     fn get_culling_mode(&self) -> CullingMode {
         self.culling_mode
     }
+
+    fn get_material(&self) -> Material {
+        self.material
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        self.mesh.bounds()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.entity_id
+    }
 }
 
 impl VertexProvider for Circle {
     fn vertices(&self) -> &[Vertex] {
         self.mesh.vertices()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        self.mesh.indices()
+    }
+
+    fn geometry_key(&self) -> u64 {
+        self.mesh.geometry_key()
+    }
 }
 
 /// Cylinder object (3D cylinder with circular cross-section)
@@ -280,6 +400,8 @@ pub struct Cylinder {
     transform: Transform,
     is_dirty: bool,
     culling_mode: CullingMode,
+    material: Material,
+    entity_id: Option<EntityId>,
 }
 
 impl Cylinder {
@@ -290,6 +412,8 @@ impl Cylinder {
             transform: Transform::new(),
             is_dirty: true,
             culling_mode: CullingMode::BackfaceCulling, // 3D objects benefit from backface culling
+            material: Material::default(),
+            entity_id: None,
         }
     }
 
@@ -312,6 +436,21 @@ impl Cylinder {
     pub fn set_culling_mode(&mut self, mode: CullingMode) {
         self.culling_mode = mode;
     }
+
+    /// Get the surface material
+    pub fn get_material(&self) -> Material {
+        self.material
+    }
+
+    /// Set the surface material; the mesh and its cached buffers are unaffected
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Set the id reported when this object is picked; scenes set it when the object is added
+    pub fn set_entity_id(&mut self, entity_id: Option<EntityId>) {
+        self.entity_id = entity_id;
+    }
 }
 
 impl Renderable for Cylinder {
@@ -347,12 +486,32 @@ impl Renderable for Cylinder {
     fn get_culling_mode(&self) -> CullingMode {
         self.culling_mode
     }
+
+    fn get_material(&self) -> Material {
+        self.material
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        self.mesh.bounds()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.entity_id
+    }
 }
 
 impl VertexProvider for Cylinder {
     fn vertices(&self) -> &[Vertex] {
         self.mesh.vertices()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        self.mesh.indices()
+    }
+
+    fn geometry_key(&self) -> u64 {
+        self.mesh.geometry_key()
+    }
 }
 
 /// Cone object (3D cone with circular base)
@@ -362,6 +521,8 @@ pub struct Cone {
     transform: Transform,
     is_dirty: bool,
     culling_mode: CullingMode,
+    material: Material,
+    entity_id: Option<EntityId>,
 }
 
 impl Cone {
@@ -372,6 +533,8 @@ impl Cone {
             transform: Transform::new(),
             is_dirty: true,
             culling_mode: CullingMode::BackfaceCulling, // 3D objects benefit from backface culling
+            material: Material::default(),
+            entity_id: None,
         }
     }
 
@@ -394,6 +557,21 @@ impl Cone {
     pub fn set_culling_mode(&mut self, mode: CullingMode) {
         self.culling_mode = mode;
     }
+
+    /// Get the surface material
+    pub fn get_material(&self) -> Material {
+        self.material
+    }
+
+    /// Set the surface material; the mesh and its cached buffers are unaffected
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Set the id reported when this object is picked; scenes set it when the object is added
+    pub fn set_entity_id(&mut self, entity_id: Option<EntityId>) {
+        self.entity_id = entity_id;
+    }
 }
 
 impl Renderable for Cone {
@@ -429,12 +607,32 @@ impl Renderable for Cone {
     fn get_culling_mode(&self) -> CullingMode {
         self.culling_mode
     }
+
+    fn get_material(&self) -> Material {
+        self.material
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        self.mesh.bounds()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.entity_id
+    }
 }
 
 impl VertexProvider for Cone {
     fn vertices(&self) -> &[Vertex] {
         self.mesh.vertices()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        self.mesh.indices()
+    }
+
+    fn geometry_key(&self) -> u64 {
+        self.mesh.geometry_key()
+    }
 }
 
 /// Sphere object (3D sphere using UV sphere generation)
@@ -444,6 +642,8 @@ pub struct Sphere {
     transform: Transform,
     is_dirty: bool,
     culling_mode: CullingMode,
+    material: Material,
+    entity_id: Option<EntityId>,
 }
 
 impl Sphere {
@@ -454,6 +654,8 @@ impl Sphere {
             transform: Transform::new(),
             is_dirty: true,
             culling_mode: CullingMode::BackfaceCulling, // 3D objects benefit from backface culling
+            material: Material::default(),
+            entity_id: None,
         }
     }
 
@@ -476,6 +678,21 @@ impl Sphere {
     pub fn set_culling_mode(&mut self, mode: CullingMode) {
         self.culling_mode = mode;
     }
+
+    /// Get the surface material
+    pub fn get_material(&self) -> Material {
+        self.material
+    }
+
+    /// Set the surface material; the mesh and its cached buffers are unaffected
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Set the id reported when this object is picked; scenes set it when the object is added
+    pub fn set_entity_id(&mut self, entity_id: Option<EntityId>) {
+        self.entity_id = entity_id;
+    }
 }
 
 impl Renderable for Sphere {
@@ -511,12 +728,32 @@ impl Renderable for Sphere {
     fn get_culling_mode(&self) -> CullingMode {
         self.culling_mode
     }
+
+    fn get_material(&self) -> Material {
+        self.material
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        self.mesh.bounds()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.entity_id
+    }
 }
 
 impl VertexProvider for Sphere {
     fn vertices(&self) -> &[Vertex] {
         self.mesh.vertices()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        self.mesh.indices()
+    }
+
+    fn geometry_key(&self) -> u64 {
+        self.mesh.geometry_key()
+    }
 }
 
 /// Trait for objects that can be rendered and updated
@@ -529,6 +766,14 @@ pub trait Renderable {
     fn get_matrix(&self) -> glam::Mat4;
     fn get_matrix_cached(&mut self) -> glam::Mat4;
     fn get_culling_mode(&self) -> CullingMode;
+    fn get_material(&self) -> Material;
+
+    /// Axis-aligned bounds of the object's geometry before its transform is applied
+    /// Returns (min, max) corners
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3);
+
+    /// Id of the scene entity this object belongs to, reported when it's picked
+    fn entity_id(&self) -> Option<EntityId>;
 
     /// Mark object as clean after GPU update (called by renderer)
     fn mark_clean(&mut self) {
@@ -568,6 +813,8 @@ pub struct Triangle {
     vertices: [Vertex; 3],
     transform: Transform,
     culling_mode: CullingMode,
+    material: Material,
+    entity_id: Option<EntityId>,
 }
 
 impl Triangle {
@@ -587,20 +834,28 @@ impl Triangle {
                 Vertex {
                     position: [0.0, top_y, 0.0],
                     color: [1.0, 0.0, 0.0],
+                    normal: [0.0, 0.0, 1.0],
+                    uv: [0.5, 0.0],
                 },
                 // Bottom-left vertex (green)
                 Vertex {
                     position: [-scale / 2.0, bottom_y, 0.0],
                     color: [0.0, 1.0, 0.0],
+                    normal: [0.0, 0.0, 1.0],
+                    uv: [0.0, 1.0],
                 },
                 // Bottom-right vertex (blue)
                 Vertex {
                     position: [scale / 2.0, bottom_y, 0.0],
                     color: [0.0, 0.0, 1.0],
+                    normal: [0.0, 0.0, 1.0],
+                    uv: [1.0, 1.0],
                 },
             ],
             transform: Transform::new(),
             culling_mode: CullingMode::None, // 2D triangles should render both sides
+            material: Material::default(),
+            entity_id: None,
         }
     }
 
@@ -623,6 +878,21 @@ impl Triangle {
     pub fn set_culling_mode(&mut self, mode: CullingMode) {
         self.culling_mode = mode;
     }
+
+    /// Get the surface material
+    pub fn get_material(&self) -> Material {
+        self.material
+    }
+
+    /// Set the surface material; the mesh and its cached buffers are unaffected
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    /// Set the id reported when this object is picked; scenes set it when the object is added
+    pub fn set_entity_id(&mut self, entity_id: Option<EntityId>) {
+        self.entity_id = entity_id;
+    }
 }
 
 impl Renderable for Triangle {
@@ -660,6 +930,24 @@ impl Renderable for Triangle {
     fn get_culling_mode(&self) -> CullingMode {
         self.culling_mode
     }
+
+    fn get_material(&self) -> Material {
+        self.material
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        self.vertices.iter().fold(
+            (glam::Vec3::splat(f32::INFINITY), glam::Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), vertex| {
+                let position = glam::Vec3::from(vertex.position);
+                (min.min(position), max.max(position))
+            },
+        )
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.entity_id
+    }
 }
 
 impl VertexProvider for Triangle {
@@ -704,12 +992,32 @@ impl<T: Renderable> Renderable for &T {
     fn get_culling_mode(&self) -> CullingMode {
         (**self).get_culling_mode()
     }
+
+    fn get_material(&self) -> Material {
+        (**self).get_material()
+    }
+
+    fn bounds(&self) -> (glam::Vec3, glam::Vec3) {
+        (**self).bounds()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        (**self).entity_id()
+    }
 }
 
 impl<T: VertexProvider> VertexProvider for &T {
     fn vertices(&self) -> &[Vertex] {
         (**self).vertices()
     }
+
+    fn indices(&self) -> Option<&Indices> {
+        (**self).indices()
+    }
+
+    fn geometry_key(&self) -> u64 {
+        (**self).geometry_key()
+    }
 }
 
 #[cfg(test)]
@@ -732,40 +1040,49 @@ mod tests {
     #[test]
     fn test_quad_creation() {
         let quad = Quad::with_size(1.0, 2.0);
-        assert_eq!(quad.vertices().len(), 6); // 2 triangles
+        assert_eq!(quad.vertices().len(), 4); // 4 shared corners
+        assert_eq!(quad.draw_count(), 6); // 2 triangles
     }
 
     #[test]
     fn test_cube_creation() {
         let cube = Cube::with_size(1.0);
-        assert_eq!(cube.vertices().len(), 36); // 12 triangles
+        assert_eq!(cube.vertices().len(), 24); // 4 corners per face
+        assert_eq!(cube.draw_count(), 36); // 12 triangles
     }
 
     #[test]
     fn test_circle_creation() {
         let circle = Circle::new(1.0, 8);
-        assert_eq!(circle.vertices().len(), 24); // 8 triangles (3 vertices each)
+        assert_eq!(circle.vertices().len(), 9); // center + 8 rim vertices
+        assert_eq!(circle.draw_count(), 24); // 8 triangles (3 indices each)
     }
 
     #[test]
     fn test_cylinder_creation() {
         let cylinder = Cylinder::new(1.0, 2.0, 6);
-        // 6 side quads (12 triangles) + 2 caps (6 triangles each) = 24 triangles = 72 vertices
-        assert_eq!(cylinder.vertices().len(), 72);
+        // 2 side rings of 7 + 2 cap rings of 6 + 2 cap centers = 28 vertices
+        assert_eq!(cylinder.vertices().len(), 28);
+        // 6 side quads (12 triangles) + 2 caps (6 triangles each) = 24 triangles = 72 indices
+        assert_eq!(cylinder.draw_count(), 72);
     }
 
     #[test]
     fn test_cone_creation() {
         let cone = Cone::new(1.0, 2.0, 6);
-        // 6 side triangles + 6 base triangles = 12 triangles = 36 vertices
-        assert_eq!(cone.vertices().len(), 36);
+        // 6 apexes + side ring of 7 + base ring of 6 + base center = 20 vertices
+        assert_eq!(cone.vertices().len(), 20);
+        // 6 side triangles + 6 base triangles = 12 triangles = 36 indices
+        assert_eq!(cone.draw_count(), 36);
     }
 
     #[test]
     fn test_sphere_creation() {
         let sphere = Sphere::new(1.0, 4, 8);
-        // 4 latitude segments * 8 longitude segments * 2 triangles per quad = 64 triangles = 192 vertices
-        assert_eq!(sphere.vertices().len(), 192);
+        // (4 + 1) latitude rings * (8 + 1) longitude columns = 45 grid vertices
+        assert_eq!(sphere.vertices().len(), 45);
+        // 4 latitude segments * 8 longitude segments * 2 triangles per quad = 64 triangles = 192 indices
+        assert_eq!(sphere.draw_count(), 192);
     }
 
     #[test]
@@ -829,6 +1146,131 @@ mod tests {
         assert_eq!(triangle.get_culling_mode(), CullingMode::None);
         assert_eq!(cube.get_culling_mode(), CullingMode::BackfaceCulling);
     }
-}
 
+    #[test]
+    fn test_geometry_key_identifies_shared_meshes() {
+        let mut moved = Sphere::new(1.0, 8, 16);
+        moved.transform_translate(5.0, 0.0, 0.0);
+
+        // Transforms don't affect the geometry, only the mesh parameters do
+        assert_eq!(Sphere::new(1.0, 8, 16).geometry_key(), moved.geometry_key());
+        assert_ne!(Sphere::new(1.0, 8, 16).geometry_key(), Sphere::new(2.0, 8, 16).geometry_key());
+        assert_ne!(Cube::with_size(1.0).geometry_key(), Sphere::new(1.0, 8, 16).geometry_key());
+        assert_eq!(Triangle::new().geometry_key(), Triangle::new().geometry_key());
+    }
+
+    #[test]
+    fn test_indices_stay_in_bounds() {
+        let meshes: Vec<Box<dyn VertexProvider>> = vec![
+            Box::new(Quad::with_size(1.0, 1.0)),
+            Box::new(Cube::with_size(1.0)),
+            Box::new(Circle::new(1.0, 8)),
+            Box::new(Cylinder::new(1.0, 2.0, 6)),
+            Box::new(Cone::new(1.0, 2.0, 6)),
+            Box::new(Sphere::new(1.0, 4, 8)),
+        ];
+
+        for mesh in &meshes {
+            let indices = mesh.indices().expect("primitive meshes are indexed");
+            assert_eq!(indices.len() % 3, 0);
+            assert_eq!(indices.format(), wgpu::IndexFormat::Uint16);
+            assert!((0..indices.len()).all(|i| (indices.get(i).unwrap() as usize) < mesh.vertex_count()));
+        }
+    }
+
+    #[test]
+    fn test_dense_sphere_uses_32_bit_indices() {
+        let sphere = Sphere::new(1.0, 256, 512);
+        assert_eq!(sphere.vertices().len(), 257 * 513);
+        assert_eq!(sphere.indices().unwrap().format(), wgpu::IndexFormat::Uint32);
+    }
 
+    #[test]
+    fn test_normals_are_unit_length() {
+        let meshes: Vec<Box<dyn VertexProvider>> = vec![
+            Box::new(Triangle::new()),
+            Box::new(Quad::with_size(1.0, 1.0)),
+            Box::new(Cube::with_size(1.0)),
+            Box::new(Circle::new(1.0, 8)),
+            Box::new(Cylinder::new(1.0, 2.0, 6)),
+            Box::new(Cone::new(1.0, 2.0, 6)),
+            Box::new(Sphere::new(1.0, 4, 8)),
+        ];
+
+        for mesh in &meshes {
+            for vertex in mesh.vertices() {
+                let length = Vec3::from(vertex.normal).length();
+                assert!((length - 1.0).abs() < 1e-5, "normal {:?} isn't unit length", vertex.normal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_mesh_normals_point_outward() {
+        let meshes: Vec<Box<dyn VertexProvider>> = vec![
+            Box::new(Cube::with_size(1.0)),
+            Box::new(Cylinder::new(1.0, 2.0, 6)),
+            Box::new(Cone::new(1.0, 2.0, 6)),
+            Box::new(Sphere::new(1.0, 4, 8)),
+        ];
+
+        // Every primitive is centered on the origin, so outward normals never face it
+        for mesh in &meshes {
+            for vertex in mesh.vertices() {
+                assert!(Vec3::from(vertex.position).dot(Vec3::from(vertex.normal)) >= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_winding_matches_normals() {
+        let meshes: Vec<Box<dyn VertexProvider>> = vec![
+            Box::new(Quad::with_size(1.0, 1.0)),
+            Box::new(Cube::with_size(1.0)),
+            Box::new(Circle::new(1.0, 8)),
+            Box::new(Cylinder::new(1.0, 2.0, 6)),
+            Box::new(Cone::new(1.0, 2.0, 6)),
+            Box::new(Sphere::new(1.0, 4, 8)),
+        ];
+
+        // Counter-clockwise triangles face the same way as their vertex normals
+        for mesh in &meshes {
+            let vertices = mesh.vertices();
+            let indices = mesh.indices().unwrap();
+            for triangle in 0..indices.len() / 3 {
+                let [a, b, c] = [0, 1, 2].map(|corner| vertices[indices.get(triangle * 3 + corner).unwrap() as usize]);
+                let face = (Vec3::from(b.position) - Vec3::from(a.position))
+                    .cross(Vec3::from(c.position) - Vec3::from(a.position));
+                if face.length() < 1e-6 {
+                    continue; // Degenerate triangles at the sphere's poles
+                }
+                let normal = Vec3::from(a.normal) + Vec3::from(b.normal) + Vec3::from(c.normal);
+                assert!(face.dot(normal) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_uvs_in_unit_square() {
+        let meshes: Vec<Box<dyn VertexProvider>> = vec![
+            Box::new(Triangle::new()),
+            Box::new(Quad::with_size(1.0, 1.0)),
+            Box::new(Cube::with_size(1.0)),
+            Box::new(Circle::new(1.0, 8)),
+            Box::new(Cylinder::new(1.0, 2.0, 6)),
+            Box::new(Cone::new(1.0, 2.0, 6)),
+            Box::new(Sphere::new(1.0, 4, 8)),
+        ];
+
+        for mesh in &meshes {
+            for vertex in mesh.vertices() {
+                assert!(vertex.uv.iter().all(|coord| (-1e-6..=1.0 + 1e-6).contains(coord)), "uv {:?}", vertex.uv);
+            }
+        }
+
+        // The side seam repeats its vertex with u = 0 and u = 1 so the texture wraps fully
+        let cylinder = Cylinder::new(1.0, 2.0, 6);
+        assert_eq!(cylinder.vertices()[0].uv, [1.0, 0.0]);
+        assert_eq!(cylinder.vertices()[6].uv, [0.0, 0.0]);
+    }
+}
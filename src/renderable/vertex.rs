@@ -1,8 +1,15 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub color: [f32; 3],
+    /// Unit surface normal in model space
+    pub normal: [f32; 3],
+    /// Texture coordinates, with (0, 0) at the top-left of the texture
+    pub uv: [f32; 2],
 }
 
 impl Vertex {
@@ -21,18 +28,126 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
             ],
         }
     }
 }
 
+/// Triangle list indices into a vertex buffer
+///
+/// Uses 16-bit indices whenever the vertex count allows it, halving the index
+/// buffer size, and falls back to 32-bit indices for big meshes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// Build indices for a mesh with `vertex_count` vertices, picking the smallest index format
+    pub fn new(indices: Vec<u32>, vertex_count: usize) -> Self {
+        if vertex_count <= u16::MAX as usize + 1 {
+            Self::U16(indices.into_iter().map(|index| index as u16).collect())
+        } else {
+            Self::U32(indices)
+        }
+    }
+
+    /// Number of indices
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index format to bind the index buffer with
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Self::U16(_) => wgpu::IndexFormat::Uint16,
+            Self::U32(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    /// Raw index data for uploading to an index buffer
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::U16(indices) => bytemuck::cast_slice(indices),
+            Self::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+
+    /// Get index `i` widened to 32 bits
+    pub fn get(&self, i: usize) -> Option<u32> {
+        match self {
+            Self::U16(indices) => indices.get(i).map(|&index| index as u32),
+            Self::U32(indices) => indices.get(i).copied(),
+        }
+    }
+}
+
 pub trait VertexProvider {
     fn vertices(&self) -> &[Vertex];
     fn vertex_count(&self) -> usize {
         self.vertices().len()
     }
 
+    /// Triangle list indices into `vertices()`, or `None` to draw the vertices in order
+    fn indices(&self) -> Option<&Indices> {
+        None
+    }
+
+    /// Number of vertices a draw of this geometry processes
+    fn draw_count(&self) -> usize {
+        self.indices().map_or(self.vertex_count(), Indices::len)
+    }
+
     fn buffer_contents(&self) -> &[u8] {
         bytemuck::cast_slice(self.vertices())
     }
+
+    /// Key identifying this geometry for vertex buffer caching and instancing
+    ///
+    /// Providers with equal keys must have identical vertex data. The default hashes
+    /// the vertex bytes; meshes override it with a cheaper key.
+    fn geometry_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.buffer_contents().hash(&mut hasher);
+        if let Some(indices) = self.indices() {
+            indices.as_bytes().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indices_pick_smallest_format() {
+        let small = Indices::new(vec![0, 1, 2], 3);
+        assert_eq!(small.format(), wgpu::IndexFormat::Uint16);
+        assert_eq!(small.as_bytes().len(), 6);
+
+        let big = Indices::new(vec![0, 1, 70_000], 70_001);
+        assert_eq!(big.format(), wgpu::IndexFormat::Uint32);
+        assert_eq!(big.as_bytes().len(), 12);
+        assert_eq!(big.get(2), Some(70_000));
+        assert_eq!(big.len(), 3);
+    }
 }
@@ -0,0 +1,185 @@
+//! Background drawing
+//!
+//! Solid and transparent backgrounds are plain clears of the render target.
+//! Gradients are drawn as a fullscreen triangle at the start of the frame's
+//! render pass, underneath every object. Skies and environment maps are drawn
+//! by [`SkyboxRenderer`](crate::renderer::skybox::SkyboxRenderer).
+
+use std::collections::HashMap;
+
+use crate::renderer::config::BackgroundMode;
+use crate::renderer::pipeline_cache::DepthState;
+
+/// Gradient colors as laid out in the shader's uniform block
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GradientUniform {
+    top: [f32; 4],
+    bottom: [f32; 4],
+}
+
+impl GradientUniform {
+    fn new(top: wgpu::Color, bottom: wgpu::Color) -> Self {
+        Self {
+            top: color_to_array(top),
+            bottom: color_to_array(bottom),
+        }
+    }
+}
+
+pub(crate) fn color_to_array(color: wgpu::Color) -> [f32; 4] {
+    [color.r as f32, color.g as f32, color.b as f32, color.a as f32]
+}
+
+/// Draws gradient backgrounds, keeping one pipeline per target format and sample count
+pub struct BackgroundRenderer {
+    shader: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipelines: HashMap<(wgpu::TextureFormat, u32), wgpu::RenderPipeline>,
+}
+
+impl BackgroundRenderer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Background Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/background.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Background Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Background Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Background Uniform Buffer"),
+            size: std::mem::size_of::<GradientUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Background Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            shader,
+            layout,
+            uniform_buffer,
+            bind_group,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Upload the background's colors and build its pipeline if needed
+    /// Returns false when the background is just a clear and nothing needs drawing
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        background: &BackgroundMode,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> bool {
+        let BackgroundMode::Gradient { top, bottom } = background else {
+            return false;
+        };
+
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&GradientUniform::new(*top, *bottom)),
+        );
+
+        let Self { shader, layout, pipelines, .. } = self;
+        pipelines
+            .entry((format, sample_count))
+            .or_insert_with(|| Self::create_pipeline(device, shader, layout, format, sample_count));
+        true
+    }
+
+    /// Draw the prepared background; it neither tests nor writes depth
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass, format: wgpu::TextureFormat, sample_count: u32) {
+        if let Some(pipeline) = self.pipelines.get(&(format, sample_count)) {
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Background Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                ..DepthState::default().to_wgpu()
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gradient_uniform_layout() {
+        let uniform = GradientUniform::new(wgpu::Color::WHITE, wgpu::Color::TRANSPARENT);
+        assert_eq!(std::mem::size_of::<GradientUniform>(), 32);
+        assert_eq!(uniform.top, [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(uniform.bottom, [0.0, 0.0, 0.0, 0.0]);
+    }
+}
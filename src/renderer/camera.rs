@@ -3,6 +3,7 @@
 //! Handles view and projection matrices for 3D rendering
 
 use glam::{Mat4, Vec3};
+use crate::renderer::frustum::Frustum;
 
 /// Projection mode for the camera
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -13,6 +14,9 @@ pub enum ProjectionMode {
     Perspective,
 }
 
+/// Vertical field of view of the perspective projection
+pub const FIELD_OF_VIEW_DEGREES: f32 = 60.0;
+
 /// Camera with projection and view matrix management
 pub struct Camera {
     pub view_matrix: Mat4,
@@ -88,6 +92,11 @@ impl Camera {
         self.projection_matrix * self.view_matrix
     }
 
+    /// The region of space this camera can see, for culling objects outside it
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_view_projection(self.get_view_projection_matrix())
+    }
+
     fn update_matrices(&mut self) {
         // Choose projection based on mode
         self.projection_matrix = match self.projection_mode {
@@ -105,7 +114,7 @@ impl Camera {
             ProjectionMode::Perspective => {
                 // Perspective projection - objects get smaller with distance
                 Mat4::perspective_rh(
-                    60.0_f32.to_radians(), // 60-degree field of view
+                    FIELD_OF_VIEW_DEGREES.to_radians(),
                     self.aspect_ratio,
                     0.1,   // near plane
                     100.0, // far plane
@@ -2,6 +2,8 @@
 //!
 //! Provides various settings for controlling rendering behavior
 
+use crate::renderer::skybox::EnvironmentId;
+
 /// Antialiasing settings for the renderer
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AntialiasingMode {
@@ -11,11 +13,25 @@ pub enum AntialiasingMode {
     Msaa2x,
     /// 4x multisampling (good quality/performance balance)
     Msaa4x,
-    /// 8x multisampling (highest quality, may be slower)
+    /// 8x multisampling (high quality, may be slower)
     Msaa8x,
+    /// 16x multisampling (highest quality, only on some desktop GPUs)
+    Msaa16x,
 }
 
+/// Depth format used by every render pass; MSAA modes must support it as well
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
 impl AntialiasingMode {
+    /// All antialiasing modes, ordered from lowest to highest sample count
+    pub const ALL: [AntialiasingMode; 5] = [
+        AntialiasingMode::None,
+        AntialiasingMode::Msaa2x,
+        AntialiasingMode::Msaa4x,
+        AntialiasingMode::Msaa8x,
+        AntialiasingMode::Msaa16x,
+    ];
+
     /// Get the sample count for this antialiasing mode
     pub fn sample_count(&self) -> u32 {
         match self {
@@ -23,6 +39,7 @@ impl AntialiasingMode {
             AntialiasingMode::Msaa2x => 2,
             AntialiasingMode::Msaa4x => 4,
             AntialiasingMode::Msaa8x => 8,
+            AntialiasingMode::Msaa16x => 16,
         }
     }
 
@@ -31,46 +48,101 @@ impl AntialiasingMode {
         self.sample_count() > 1
     }
 
-    /// Validate if this antialiasing mode is supported by the device for the given format
-    pub fn is_supported(&self, _device: &wgpu::Device, _format: wgpu::TextureFormat) -> bool {
-        if !self.is_multisampled() {
-            return true; // Single sampling is always supported
+    /// Validate if this antialiasing mode is supported by the adapter and device for the given format
+    /// Both the color format and the renderer's depth format must support the sample count
+    pub fn is_supported(&self, adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat) -> bool {
+        Self::get_supported_modes(adapter, device, format).contains(self)
+    }
+
+    /// Get the highest supported antialiasing mode for the adapter and device
+    pub fn get_best_supported(adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self::get_supported_modes(adapter, device, format)
+            .last()
+            .copied()
+            .unwrap_or(AntialiasingMode::None)
+    }
+
+    /// Get all supported antialiasing modes for the adapter and device, lowest sample count first
+    ///
+    /// The adapter's sample counts other than 1 and 4 are only usable when the device was created
+    /// with `Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`. Both renderers request it when the
+    /// adapter has it; devices without it are limited to the sample counts WebGPU guarantees.
+    pub fn get_supported_modes(adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat) -> Vec<Self> {
+        let color_flags = adapter.get_texture_format_features(format).flags;
+        let depth_flags = adapter.get_texture_format_features(DEPTH_FORMAT).flags;
+        Self::supported_by_flags(Self::usable_flags(color_flags & depth_flags, device.features()))
+    }
+
+    /// The adapter's format feature flags limited to the sample counts a device with these features can use
+    fn usable_flags(flags: wgpu::TextureFormatFeatureFlags, features: wgpu::Features) -> wgpu::TextureFormatFeatureFlags {
+        if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            flags
+        } else {
+            flags & wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X4
         }
+    }
 
-        // Check if the device supports this sample count for the format
-        // We'll try to create a test texture descriptor and see if it would be valid
-        let sample_count = self.sample_count();
-
-        // For now, we'll use a conservative approach
-        // Most devices support 1, 2, and 4 samples. 8 samples is less common.
-        match sample_count {
-            1 => true,  // Always supported
-            2 => true,  // Usually supported
-            4 => true,  // WebGPU spec guarantees this
-            8 => false, // Not guaranteed, device-specific
-            _ => false,
+    /// Modes whose sample count is allowed by the given format feature flags
+    pub fn supported_by_flags(flags: wgpu::TextureFormatFeatureFlags) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|mode| !mode.is_multisampled() || flags.sample_count_supported(mode.sample_count()))
+            .collect()
+    }
+
+    /// Check the mode against the adapter and device, returning a descriptive error if it can't be used
+    pub fn validate(self, adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat) -> Result<Self, ConfigError> {
+        let supported = Self::get_supported_modes(adapter, device, format);
+        if supported.contains(&self) {
+            Ok(self)
+        } else {
+            Err(ConfigError::UnsupportedAntialiasing {
+                requested: self,
+                format,
+                supported,
+            })
         }
     }
 
-    /// Get the highest supported antialiasing mode for the device (conservative)
-    pub fn get_best_supported(_device: &wgpu::Device, _format: wgpu::TextureFormat) -> Self {
-        // For now, return 4x MSAA as it's guaranteed by WebGPU spec
-        AntialiasingMode::Msaa4x
+    /// Get the highest supported mode that doesn't exceed this one's sample count
+    pub fn downgrade_to_supported(self, adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self::get_supported_modes(adapter, device, format)
+            .into_iter()
+            .rfind(|mode| mode.sample_count() <= self.sample_count())
+            .unwrap_or(AntialiasingMode::None)
     }
+}
 
-    /// Get all supported antialiasing modes for the device (conservative)
-    pub fn get_supported_modes(_device: &wgpu::Device, _format: wgpu::TextureFormat) -> Vec<Self> {
-        vec![
-            AntialiasingMode::None,
-            AntialiasingMode::Msaa2x,
-            AntialiasingMode::Msaa4x,
-            // Skip 8x for now as it's not universally supported
-        ]
+/// Errors produced when a rendering configuration can't be applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The device can't multisample the render target format at the requested sample count
+    UnsupportedAntialiasing {
+        requested: AntialiasingMode,
+        format: wgpu::TextureFormat,
+        supported: Vec<AntialiasingMode>,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::UnsupportedAntialiasing { requested, format, supported } => write!(
+                f,
+                "Antialiasing mode {:?} ({} samples) is not supported for {:?}; supported modes: {:?}",
+                requested,
+                requested.sample_count(),
+                format,
+                supported
+            ),
+        }
     }
 }
 
+impl std::error::Error for ConfigError {}
+
 /// Culling mode for controlling which faces are rendered
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum CullingMode {
     /// Render both front and back faces (good for 2D objects)
     None,
@@ -91,6 +163,147 @@ impl CullingMode {
     }
 }
 
+/// How objects are drawn, for diagnosing meshes and transforms
+///
+/// Every mode other than [`RenderMode::Shaded`] replaces the objects' materials
+/// and shaders with an unlit debug view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RenderMode {
+    /// Draw with each object's material and shader
+    #[default]
+    Shaded,
+    /// Draw only triangle edges in the objects' base colors
+    ///
+    /// Uses line polygons where the device supports them, and a shader that
+    /// keeps only the fragments near each edge elsewhere, such as on WebGL.
+    Wireframe,
+    /// Draw only vertices, as single pixels in the objects' base colors
+    Points,
+    /// Shade by distance from the camera, from black up close to white far away
+    Depth,
+    /// Shade by world-space normal, mapping each axis from -1..1 to 0..1
+    Normals,
+    /// Shade by texture coordinates, red for u and green for v
+    Uvs,
+    /// Give each entity its own color, and objects without one gray
+    ObjectIds,
+}
+
+/// What the render target shows behind the rendered objects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackgroundMode {
+    /// Clear to a single color
+    Solid(wgpu::Color),
+    /// Clear to transparent black, for compositing renders over other imagery
+    Transparent,
+    /// Vertical gradient from the top of the frame to the bottom
+    Gradient { top: wgpu::Color, bottom: wgpu::Color },
+    /// Procedural sky that follows the camera's view direction, blending from the
+    /// horizon up to the zenith and down to the ground
+    Sky {
+        zenith: wgpu::Color,
+        horizon: wgpu::Color,
+        ground: wgpu::Color,
+    },
+    /// Cubemap or equirectangular panorama loaded by the renderer, seen in every direction
+    Environment(EnvironmentId),
+}
+
+impl BackgroundMode {
+    /// The renderer's original dark blue background
+    pub const DEFAULT_COLOR: wgpu::Color = wgpu::Color {
+        r: 0.1,
+        g: 0.2,
+        b: 0.3,
+        a: 1.0,
+    };
+
+    /// Color the target is cleared to before drawing
+    /// Gradients and skies are drawn over the whole target, so their clear color is never visible
+    pub fn clear_color(&self) -> wgpu::Color {
+        match self {
+            BackgroundMode::Solid(color) => *color,
+            BackgroundMode::Transparent => wgpu::Color::TRANSPARENT,
+            BackgroundMode::Gradient { bottom, .. } => *bottom,
+            BackgroundMode::Sky { horizon, .. } => *horizon,
+            BackgroundMode::Environment(_) => wgpu::Color::BLACK,
+        }
+    }
+}
+
+impl Default for BackgroundMode {
+    fn default() -> Self {
+        BackgroundMode::Solid(Self::DEFAULT_COLOR)
+    }
+}
+
+/// Curve that maps scene colors into the displayable 0..1 range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TonemapOperator {
+    /// `c / (1 + c)`, gentle and never fully saturates
+    Reinhard,
+    /// Filmic curve fitted to the ACES reference transform, with more contrast
+    Aces,
+}
+
+/// Format objects are drawn into when HDR rendering is enabled
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// High dynamic range rendering
+///
+/// Objects are drawn into an [`HDR_FORMAT`] texture, where lit colors may go
+/// above 1. After any post effects, the frame is scaled by `exposure` and
+/// tonemapped into the output's displayable range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrConfig {
+    pub tonemap: TonemapOperator,
+    pub exposure: f32,
+}
+
+impl Default for HdrConfig {
+    fn default() -> Self {
+        Self {
+            tonemap: TonemapOperator::Aces,
+            exposure: 1.0,
+        }
+    }
+}
+
+impl HdrConfig {
+    pub fn new(tonemap: TonemapOperator, exposure: f32) -> Self {
+        Self { tonemap, exposure }
+    }
+
+    /// The tonemap pass that ends an HDR frame
+    pub fn tonemap_effect(&self) -> PostEffect {
+        PostEffect::Tonemap {
+            operator: self.tonemap,
+            exposure: self.exposure,
+        }
+    }
+}
+
+/// A fullscreen effect applied to the finished frame
+///
+/// Effects run in the order they're listed in [`RenderConfig::post_processing`],
+/// each one reading the previous effect's output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostEffect {
+    /// Fast approximate antialiasing, smoothing edges without multisampling
+    Fxaa,
+    /// Scale colors by `exposure`, then compress them with the operator
+    Tonemap { operator: TonemapOperator, exposure: f32 },
+    /// Darken the frame towards its corners
+    ///
+    /// `radius` is where darkening starts, from 0 at the center to 1 at the
+    /// corners, and `strength` is how dark the corners get, from 0 to 1.
+    Vignette { strength: f32, radius: f32 },
+    /// Blend towards the frame's luminance; 1 removes all color
+    Grayscale { amount: f32 },
+    /// Separable gaussian blur with a standard deviation of `sigma` pixels
+    GaussianBlur { sigma: f32 },
+}
+
 /// Complete rendering configuration
 #[derive(Debug, Clone)]
 pub struct RenderConfig {
@@ -98,8 +311,19 @@ pub struct RenderConfig {
     pub antialiasing: AntialiasingMode,
     /// Face culling mode
     pub culling: CullingMode,
-    /// Whether to use alpha blending
+    /// Whether to alpha blend opaque objects too, for textures with transparent texels
+    /// Objects with a material opacity below 1 are always blended, in a sorted pass of their own
     pub alpha_blending: bool,
+    /// Background behind the rendered objects
+    pub background: BackgroundMode,
+    /// Effects applied in order to the finished frame; empty renders straight to the target
+    pub post_processing: Vec<PostEffect>,
+    /// Render in high dynamic range and tonemap to the target; `None` draws to the target's format
+    pub hdr: Option<HdrConfig>,
+    /// Draw each object's entity id into an ID buffer so it can be picked under the cursor
+    pub picking: bool,
+    /// Shaded, or one of the debug views
+    pub render_mode: RenderMode,
 }
 
 impl Default for RenderConfig {
@@ -108,6 +332,11 @@ impl Default for RenderConfig {
             antialiasing: AntialiasingMode::Msaa4x,
             culling: CullingMode::BackfaceCulling,
             alpha_blending: false,
+            background: BackgroundMode::default(),
+            post_processing: Vec::new(),
+            hdr: None,
+            picking: false,
+            render_mode: RenderMode::Shaded,
         }
     }
 }
@@ -119,6 +348,11 @@ impl RenderConfig {
             antialiasing: AntialiasingMode::Msaa4x,
             culling: CullingMode::None, // Render both sides for 2D objects
             alpha_blending: true,
+            background: BackgroundMode::default(),
+            post_processing: Vec::new(),
+            hdr: None,
+            picking: false,
+            render_mode: RenderMode::Shaded,
         }
     }
 
@@ -128,6 +362,11 @@ impl RenderConfig {
             antialiasing: AntialiasingMode::Msaa4x,
             culling: CullingMode::BackfaceCulling, // Cull back faces for performance
             alpha_blending: false,
+            background: BackgroundMode::default(),
+            post_processing: Vec::new(),
+            hdr: None,
+            picking: false,
+            render_mode: RenderMode::Shaded,
         }
     }
 
@@ -137,8 +376,54 @@ impl RenderConfig {
             antialiasing: AntialiasingMode::None,
             culling: CullingMode::BackfaceCulling,
             alpha_blending: false,
+            background: BackgroundMode::default(),
+            post_processing: Vec::new(),
+            hdr: None,
+            picking: false,
+            render_mode: RenderMode::Shaded,
         }
     }
+
+    /// Use the given background instead of the default solid color
+    pub fn with_background(mut self, background: BackgroundMode) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Append an effect to the end of the post-processing chain
+    pub fn with_post_effect(mut self, effect: PostEffect) -> Self {
+        self.post_processing.push(effect);
+        self
+    }
+
+    /// Replace the whole post-processing chain
+    pub fn with_post_processing(mut self, effects: Vec<PostEffect>) -> Self {
+        self.post_processing = effects;
+        self
+    }
+
+    /// Render in high dynamic range, tonemapping after the post-processing chain
+    pub fn with_hdr(mut self, hdr: HdrConfig) -> Self {
+        self.hdr = Some(hdr);
+        self
+    }
+
+    /// Draw entity ids alongside color so objects can be picked
+    pub fn with_picking(mut self) -> Self {
+        self.picking = true;
+        self
+    }
+
+    /// Draw objects in a debug view instead of shaded
+    pub fn with_render_mode(mut self, render_mode: RenderMode) -> Self {
+        self.render_mode = render_mode;
+        self
+    }
+
+    /// Format objects are drawn into for a given output format
+    pub fn scene_format(&self, output_format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        if self.hdr.is_some() { HDR_FORMAT } else { output_format }
+    }
 }
 
 #[cfg(test)]
@@ -150,7 +435,14 @@ mod tests {
         let config = RenderConfig::default();
         assert_eq!(config.antialiasing, AntialiasingMode::Msaa4x);
         assert_eq!(config.culling, CullingMode::BackfaceCulling);
-        assert_eq!(config.alpha_blending, false);
+        assert!(!config.alpha_blending);
+        assert!(!config.picking);
+        assert!(RenderConfig::for_3d().with_picking().picking);
+        assert_eq!(config.render_mode, RenderMode::Shaded);
+        assert_eq!(
+            RenderConfig::for_2d().with_render_mode(RenderMode::Normals).render_mode,
+            RenderMode::Normals
+        );
     }
 
     #[test]
@@ -158,7 +450,7 @@ mod tests {
         let config = RenderConfig::for_2d();
         assert_eq!(config.antialiasing, AntialiasingMode::Msaa4x);
         assert_eq!(config.culling, CullingMode::None);
-        assert_eq!(config.alpha_blending, true);
+        assert!(config.alpha_blending);
     }
 
     #[test]
@@ -166,7 +458,7 @@ mod tests {
         let config = RenderConfig::for_3d();
         assert_eq!(config.antialiasing, AntialiasingMode::Msaa4x);
         assert_eq!(config.culling, CullingMode::BackfaceCulling);
-        assert_eq!(config.alpha_blending, false);
+        assert!(!config.alpha_blending);
     }
 
     #[test]
@@ -174,7 +466,47 @@ mod tests {
         let config = RenderConfig::performance();
         assert_eq!(config.antialiasing, AntialiasingMode::None);
         assert_eq!(config.culling, CullingMode::BackfaceCulling);
-        assert_eq!(config.alpha_blending, false);
+        assert!(!config.alpha_blending);
+    }
+
+    #[test]
+    fn test_background_modes() {
+        let config = RenderConfig::default();
+        assert_eq!(config.background, BackgroundMode::Solid(BackgroundMode::DEFAULT_COLOR));
+        assert_eq!(config.background.clear_color(), BackgroundMode::DEFAULT_COLOR);
+
+        let config = RenderConfig::for_2d().with_background(BackgroundMode::Transparent);
+        assert_eq!(config.background.clear_color().a, 0.0);
+        assert!(config.alpha_blending);
+    }
+
+    #[test]
+    fn test_post_effects_keep_their_order() {
+        assert!(RenderConfig::default().post_processing.is_empty());
+
+        let config = RenderConfig::for_3d()
+            .with_post_effect(PostEffect::Fxaa)
+            .with_post_effect(PostEffect::Grayscale { amount: 1.0 });
+        assert_eq!(config.post_processing, vec![PostEffect::Fxaa, PostEffect::Grayscale { amount: 1.0 }]);
+
+        let config = config.with_post_processing(vec![PostEffect::GaussianBlur { sigma: 2.0 }]);
+        assert_eq!(config.post_processing, vec![PostEffect::GaussianBlur { sigma: 2.0 }]);
+    }
+
+    #[test]
+    fn test_hdr_changes_scene_format() {
+        let output = wgpu::TextureFormat::Bgra8UnormSrgb;
+        assert_eq!(RenderConfig::default().scene_format(output), output);
+
+        let config = RenderConfig::default().with_hdr(HdrConfig::new(TonemapOperator::Reinhard, 2.0));
+        assert_eq!(config.scene_format(output), HDR_FORMAT);
+        assert_eq!(
+            config.hdr.unwrap().tonemap_effect(),
+            PostEffect::Tonemap {
+                operator: TonemapOperator::Reinhard,
+                exposure: 2.0,
+            }
+        );
     }
 
     #[test]
@@ -183,14 +515,64 @@ mod tests {
         assert_eq!(AntialiasingMode::Msaa2x.sample_count(), 2);
         assert_eq!(AntialiasingMode::Msaa4x.sample_count(), 4);
         assert_eq!(AntialiasingMode::Msaa8x.sample_count(), 8);
+        assert_eq!(AntialiasingMode::Msaa16x.sample_count(), 16);
+    }
+
+    #[test]
+    fn test_supported_modes_from_format_flags() {
+        use wgpu::TextureFormatFeatureFlags as Flags;
+
+        // Single sampling is always available
+        assert_eq!(AntialiasingMode::supported_by_flags(Flags::empty()), vec![AntialiasingMode::None]);
+
+        // Only the advertised sample counts are reported, including 8x and 16x
+        let flags = Flags::MULTISAMPLE_X4 | Flags::MULTISAMPLE_X8 | Flags::MULTISAMPLE_X16;
+        assert_eq!(
+            AntialiasingMode::supported_by_flags(flags),
+            vec![
+                AntialiasingMode::None,
+                AntialiasingMode::Msaa4x,
+                AntialiasingMode::Msaa8x,
+                AntialiasingMode::Msaa16x,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_devices_without_adapter_specific_features_get_baseline_sample_counts() {
+        use wgpu::TextureFormatFeatureFlags as Flags;
+
+        let flags = Flags::MULTISAMPLE_X2 | Flags::MULTISAMPLE_X4 | Flags::MULTISAMPLE_X8 | Flags::MULTISAMPLE_X16;
+        let baseline = AntialiasingMode::usable_flags(flags, wgpu::Features::empty());
+        assert_eq!(
+            AntialiasingMode::supported_by_flags(baseline),
+            vec![AntialiasingMode::None, AntialiasingMode::Msaa4x]
+        );
+
+        let full = AntialiasingMode::usable_flags(flags, wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        assert_eq!(AntialiasingMode::supported_by_flags(full), AntialiasingMode::ALL.to_vec());
+    }
+
+    #[test]
+    fn test_unsupported_antialiasing_error_message() {
+        let error = ConfigError::UnsupportedAntialiasing {
+            requested: AntialiasingMode::Msaa8x,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            supported: vec![AntialiasingMode::None, AntialiasingMode::Msaa4x],
+        };
+        let message = error.to_string();
+        assert!(message.contains("Msaa8x"));
+        assert!(message.contains("8 samples"));
+        assert!(message.contains("Bgra8UnormSrgb"));
     }
 
     #[test]
     fn test_antialiasing_mode_is_multisampled() {
-        assert_eq!(AntialiasingMode::None.is_multisampled(), false);
-        assert_eq!(AntialiasingMode::Msaa2x.is_multisampled(), true);
-        assert_eq!(AntialiasingMode::Msaa4x.is_multisampled(), true);
-        assert_eq!(AntialiasingMode::Msaa8x.is_multisampled(), true);
+        assert!(!AntialiasingMode::None.is_multisampled());
+        assert!(AntialiasingMode::Msaa2x.is_multisampled());
+        assert!(AntialiasingMode::Msaa4x.is_multisampled());
+        assert!(AntialiasingMode::Msaa8x.is_multisampled());
+        assert!(AntialiasingMode::Msaa16x.is_multisampled());
     }
 
     #[test]
@@ -217,7 +599,8 @@ mod tests {
     #[test]
     fn test_config_modification() {
         let mut config = RenderConfig::default();
-        
+        assert_eq!(config.antialiasing, AntialiasingMode::Msaa4x);
+
         config.antialiasing = AntialiasingMode::None;
         assert_eq!(config.antialiasing, AntialiasingMode::None);
         
@@ -225,7 +608,7 @@ mod tests {
         assert_eq!(config.culling, CullingMode::None);
         
         config.alpha_blending = true;
-        assert_eq!(config.alpha_blending, true);
+        assert!(config.alpha_blending);
     }
 
     #[test]
@@ -241,7 +624,7 @@ mod tests {
         for config in &configs {
             // Verify sample count is valid
             let sample_count = config.antialiasing.sample_count();
-            assert!(sample_count >= 1 && sample_count <= 8);
+            assert!((1..=8).contains(&sample_count));
             assert!(sample_count.is_power_of_two());
         }
     }
@@ -0,0 +1,335 @@
+//! Immediate-mode debug lines
+//!
+//! Lines, arrows, grids, axis triads and boxes queued on a [`DebugDraw`] are drawn
+//! over the scene by the next render and then cleared, so anything that should stay
+//! visible is queued again every frame. They're depth tested against the scene
+//! without hiding anything, and drawn with a `LineList` pipeline by [`DebugLineRenderer`].
+
+use std::collections::HashMap;
+use std::mem;
+
+use glam::{Mat4, Vec3};
+
+use crate::renderable::Renderable;
+use crate::renderer::dynamic_uniforms::grown_capacity;
+use crate::renderer::pipeline_cache::DepthState;
+
+/// Number of line vertices allocated up front, before any growth
+const INITIAL_CAPACITY: usize = 1024;
+
+/// Colors of the X, Y and Z axes in [`DebugDraw::axes`]
+pub const AXIS_COLORS: [[f32; 3]; 3] = [[1.0, 0.2, 0.2], [0.2, 1.0, 0.2], [0.2, 0.4, 1.0]];
+
+/// One end of a debug line
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineVertex {
+    /// World-space position
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+}
+
+impl LineVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Lines queued for the next frame
+#[derive(Debug, Clone, Default)]
+pub struct DebugDraw {
+    vertices: Vec<LineVertex>,
+    /// Color to outline every rendered object's mesh bounds in, placed by its model matrix,
+    /// or `None` to leave them out. Unlike queued lines this stays set between frames.
+    pub bounds_color: Option<[f32; 3]>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a line segment between two world-space points
+    pub fn line(&mut self, start: Vec3, end: Vec3, color: [f32; 3]) {
+        self.vertices.push(LineVertex { position: start.to_array(), color });
+        self.vertices.push(LineVertex { position: end.to_array(), color });
+    }
+
+    /// Queue a line from `start` to `end` with a four-pronged head at `end`
+    pub fn arrow(&mut self, start: Vec3, end: Vec3, color: [f32; 3]) {
+        self.line(start, end, color);
+
+        let Some(direction) = (end - start).try_normalize() else {
+            return;
+        };
+        let head_length = (end - start).length() * 0.2;
+        let head_base = end - direction * head_length;
+        let (side, up) = direction.any_orthonormal_pair();
+        for spread in [side, -side, up, -up] {
+            self.line(end, head_base + spread * head_length * 0.5, color);
+        }
+    }
+
+    /// Queue a square grid on the world's XZ plane through `center`, `size` wide
+    /// and split into `divisions` cells along each side
+    pub fn grid(&mut self, center: Vec3, size: f32, divisions: u32, color: [f32; 3]) {
+        let divisions = divisions.max(1);
+        let half_size = size * 0.5;
+        for i in 0..=divisions {
+            let offset = -half_size + size * i as f32 / divisions as f32;
+            self.line(
+                center + Vec3::new(offset, 0.0, -half_size),
+                center + Vec3::new(offset, 0.0, half_size),
+                color,
+            );
+            self.line(
+                center + Vec3::new(-half_size, 0.0, offset),
+                center + Vec3::new(half_size, 0.0, offset),
+                color,
+            );
+        }
+    }
+
+    /// Queue a transform's X, Y and Z axes, `length` long before the transform's scale,
+    /// in red, green and blue
+    pub fn axes(&mut self, transform: Mat4, length: f32) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        for (axis, color) in [Vec3::X, Vec3::Y, Vec3::Z].into_iter().zip(AXIS_COLORS) {
+            self.line(origin, transform.transform_point3(axis * length), color);
+        }
+    }
+
+    /// Queue the twelve edges of a world-space axis-aligned box
+    pub fn aabb(&mut self, min: Vec3, max: Vec3, color: [f32; 3]) {
+        self.transformed_box(min, max, Mat4::IDENTITY, color);
+    }
+
+    /// Queue the twelve edges of a local-space box placed by `transform`,
+    /// such as a mesh's bounds under its model matrix
+    pub fn transformed_box(&mut self, min: Vec3, max: Vec3, transform: Mat4, color: [f32; 3]) {
+        // Corner i takes max along each axis whose bit is set in i
+        let corners: [Vec3; 8] = std::array::from_fn(|i| {
+            transform.transform_point3(Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            ))
+        });
+        // Each edge joins two corners differing in a single bit
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corners[i], corners[i | bit], color);
+                }
+            }
+        }
+    }
+
+    /// Queue the bounds of each object if [`Self::bounds_color`] is set
+    pub(crate) fn add_bounds<T: Renderable>(&mut self, objects: &[&T]) {
+        let Some(color) = self.bounds_color else {
+            return;
+        };
+        for object in objects {
+            let (min, max) = object.bounds();
+            self.transformed_box(min, max, object.get_matrix(), color);
+        }
+    }
+
+    /// Queued line vertices, two per line
+    pub fn vertices(&self) -> &[LineVertex] {
+        &self.vertices
+    }
+
+    /// Number of lines queued
+    pub fn line_count(&self) -> usize {
+        self.vertices.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Drop every queued line, keeping [`Self::bounds_color`]
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+}
+
+/// Draws queued debug lines, keeping one pipeline per target format and sample count
+pub struct DebugLineRenderer {
+    shader: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    vertex_count: u32,
+    pipelines: HashMap<(wgpu::TextureFormat, u32), wgpu::RenderPipeline>,
+}
+
+impl DebugLineRenderer {
+    /// Create the renderer, drawing with the camera bound through `camera_layout` like the main pass
+    pub fn new(device: &wgpu::Device, camera_layout: &wgpu::BindGroupLayout) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug Line Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/lines.wgsl").into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Line Pipeline Layout"),
+            bind_group_layouts: &[camera_layout],
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            shader,
+            layout,
+            buffer: Self::create_buffer(device, INITIAL_CAPACITY),
+            capacity: INITIAL_CAPACITY,
+            vertex_count: 0,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Line Vertex Buffer"),
+            size: (mem::size_of::<LineVertex>() * capacity) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Upload the queued lines and build the pipeline if needed
+    /// Returns false when there's nothing to draw
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        debug_draw: &DebugDraw,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> bool {
+        let vertices = debug_draw.vertices();
+        self.vertex_count = vertices.len() as u32;
+        if vertices.is_empty() {
+            return false;
+        }
+
+        if vertices.len() > self.capacity {
+            let capacity = grown_capacity(self.capacity, vertices.len());
+            log::debug!("Growing debug line buffer from {} to {} vertices", self.capacity, capacity);
+            self.buffer = Self::create_buffer(device, capacity);
+            self.capacity = capacity;
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(vertices));
+
+        let Self { shader, layout, pipelines, .. } = self;
+        pipelines
+            .entry((format, sample_count))
+            .or_insert_with(|| Self::create_pipeline(device, shader, layout, format, sample_count));
+        true
+    }
+
+    /// Draw the prepared lines; the camera must already be bound at group 0
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass, format: wgpu::TextureFormat, sample_count: u32) {
+        if let Some(pipeline) = self.pipelines.get(&(format, sample_count)) {
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Line Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[LineVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            // Hidden behind objects, but never hiding anything drawn after them
+            depth_stencil: Some(wgpu::DepthStencilState {
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                ..DepthState::default().to_wgpu()
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes_queue_their_edges() {
+        let mut debug_draw = DebugDraw::new();
+        debug_draw.aabb(Vec3::ZERO, Vec3::ONE, [1.0; 3]);
+        assert_eq!(debug_draw.line_count(), 12);
+        // Every edge is one unit long and parallel to an axis
+        for line in debug_draw.vertices().chunks(2) {
+            let delta = Vec3::from(line[1].position) - Vec3::from(line[0].position);
+            assert_eq!(delta.abs().max_element(), 1.0);
+            assert_eq!(delta.length(), 1.0);
+        }
+
+        debug_draw.clear();
+        debug_draw.grid(Vec3::ZERO, 2.0, 4, [1.0; 3]);
+        assert_eq!(debug_draw.line_count(), 10);
+        assert!(debug_draw.vertices().iter().all(|vertex| vertex.position[1] == 0.0));
+
+        debug_draw.clear();
+        debug_draw.arrow(Vec3::ZERO, Vec3::X, [1.0; 3]);
+        assert_eq!(debug_draw.line_count(), 5);
+        debug_draw.arrow(Vec3::ONE, Vec3::ONE, [1.0; 3]);
+        assert_eq!(debug_draw.line_count(), 6);
+    }
+
+    #[test]
+    fn test_axes_follow_the_transform() {
+        let mut debug_draw = DebugDraw::new();
+        let transform = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0)) * Mat4::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        debug_draw.axes(transform, 2.0);
+
+        let ends: Vec<Vec3> = debug_draw.vertices().chunks(2).map(|line| Vec3::from(line[1].position)).collect();
+        assert!(ends[0].abs_diff_eq(Vec3::new(1.0, 2.0, 1.0), 1e-5));
+        assert!(ends[1].abs_diff_eq(Vec3::new(1.0, 4.0, 3.0), 1e-5));
+        assert!(ends[2].abs_diff_eq(Vec3::new(3.0, 2.0, 3.0), 1e-5));
+        assert_eq!(debug_draw.vertices()[0].color, AXIS_COLORS[0]);
+    }
+}
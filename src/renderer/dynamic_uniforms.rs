@@ -1,37 +1,32 @@
 //! Dynamic uniform buffer system for efficient batch rendering
 //!
-//! This module provides multiple uniform buffer slots to avoid overwriting
-//! uniform data when rendering multiple objects in a single render pass.
+//! This module packs one uniform slot per object into a single buffer and
+//! addresses the slots with dynamic offsets, so uniform data isn't overwritten
+//! when rendering multiple objects in a single render pass. The buffer grows
+//! on demand when a frame needs more slots than it currently holds.
 
 use std::mem;
 
-/// Maximum number of objects that can be rendered in a single batch
-const MAX_OBJECTS_PER_BATCH: usize = 64;
+/// Number of slots allocated up front, before any growth
+const INITIAL_CAPACITY: usize = 64;
 
 /// Dynamic uniform buffer manager
 pub struct DynamicUniformBuffer {
     buffer: wgpu::Buffer,
     bind_group_layout: wgpu::BindGroupLayout,
-    bind_groups: Vec<wgpu::BindGroup>,
+    bind_group: wgpu::BindGroup,
+    matrix_size: u64,
     uniform_size: u64,
+    capacity: usize,
     current_slot: usize,
 }
 
 impl DynamicUniformBuffer {
     pub fn new(device: &wgpu::Device) -> Self {
-        // Calculate aligned uniform size (must be aligned to 256 bytes for uniform buffers)
+        // Calculate aligned uniform size (must be aligned to the device's dynamic offset alignment)
         let matrix_size = mem::size_of::<[[f32; 4]; 4]>() as u64;
         let uniform_alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
-        let uniform_size = ((matrix_size + uniform_alignment - 1) / uniform_alignment) * uniform_alignment;
-
-        // Create large buffer that can hold multiple uniforms
-        let buffer_size = uniform_size * MAX_OBJECTS_PER_BATCH as u64;
-        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Dynamic Uniform Buffer"),
-            size: buffer_size,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let uniform_size = matrix_size.div_ceil(uniform_alignment) * uniform_alignment;
 
         // Create bind group layout
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -48,33 +43,52 @@ impl DynamicUniformBuffer {
             }],
         });
 
-        // Create bind groups for each slot
-        let mut bind_groups = Vec::new();
-        for i in 0..MAX_OBJECTS_PER_BATCH {
-            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some(&format!("Dynamic Uniform Bind Group {}", i)),
-                layout: &bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &buffer,
-                        offset: 0, // Will use dynamic offset instead
-                        size: Some(std::num::NonZeroU64::new(matrix_size).unwrap()),
-                    }),
-                }],
-            });
-            bind_groups.push(bind_group);
-        }
+        let (buffer, bind_group) =
+            Self::create_storage(device, &bind_group_layout, matrix_size, uniform_size, INITIAL_CAPACITY);
 
         Self {
             buffer,
             bind_group_layout,
-            bind_groups,
+            bind_group,
+            matrix_size,
             uniform_size,
+            capacity: INITIAL_CAPACITY,
             current_slot: 0,
         }
     }
 
+    /// Create a buffer with room for `capacity` slots and the single bind group that addresses it
+    fn create_storage(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        matrix_size: u64,
+        uniform_size: u64,
+        capacity: usize,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Dynamic Uniform Buffer"),
+            size: uniform_size * capacity as u64,
+            // COPY_SRC lets slots written earlier in the frame move into a grown buffer
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Dynamic Uniform Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0, // Will use dynamic offset instead
+                    size: Some(std::num::NonZeroU64::new(matrix_size).unwrap()),
+                }),
+            }],
+        });
+
+        (buffer, bind_group)
+    }
+
     /// Reset for new frame
     pub fn reset_frame(&mut self) {
         self.current_slot = 0;
@@ -85,32 +99,129 @@ impl DynamicUniformBuffer {
         &self.bind_group_layout
     }
 
-    /// Upload multiple matrices in a batch operation
-    pub fn upload_matrices(&mut self, queue: &wgpu::Queue, matrices: &[glam::Mat4]) -> Vec<(&wgpu::BindGroup, u32)> {
-        let mut result = Vec::new();
-        
-        for matrix in matrices.iter() {
-            if self.current_slot >= MAX_OBJECTS_PER_BATCH {
-                log::warn!("Dynamic uniform buffer full! Cannot render more than {} objects per batch", MAX_OBJECTS_PER_BATCH);
-                break;
-            }
-
-            let offset = self.current_slot as u64 * self.uniform_size;
-            
-            // Upload matrix data to the correct slot
-            queue.write_buffer(
-                &self.buffer,
-                offset,
-                bytemuck::cast_slice(matrix.as_ref()),
-            );
-
-            let bind_group = &self.bind_groups[0]; // Use first bind group with dynamic offset
-            let dynamic_offset = offset as u32;
-            
-            result.push((bind_group, dynamic_offset));
-            self.current_slot += 1;
+    /// Get the bind group shared by every slot; select a slot with its dynamic offset
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Number of slots the buffer can currently hold
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Make sure the buffer can hold `required` slots, reallocating if it can't
+    ///
+    /// Slots already written this frame are copied into the new buffer on the GPU at the
+    /// same offsets, so offsets returned earlier in the frame stay valid with [`Self::bind_group`].
+    /// The bind group itself is replaced, so fetch it again after uploading.
+    fn ensure_capacity(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, required: usize) {
+        if required <= self.capacity {
+            return;
+        }
+
+        let capacity = grown_capacity(self.capacity, required);
+        log::debug!("Growing dynamic uniform buffer from {} to {} slots", self.capacity, capacity);
+
+        let (buffer, bind_group) = Self::create_storage(
+            device,
+            &self.bind_group_layout,
+            self.matrix_size,
+            self.uniform_size,
+            capacity,
+        );
+        if self.current_slot > 0 {
+            // Pending writes to the old buffer land before this submission's copy runs
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Dynamic Uniform Growth Encoder"),
+            });
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, self.current_slot as u64 * self.uniform_size);
+            queue.submit(std::iter::once(encoder.finish()));
+        }
+        self.buffer = buffer;
+        self.bind_group = bind_group;
+        self.capacity = capacity;
+    }
+
+    /// Upload multiple matrices in a batch operation, returning the dynamic offset of each one
+    ///
+    /// All matrices are written to the buffer with a single queue write; use the returned
+    /// offsets together with [`Self::bind_group`].
+    pub fn upload_matrices(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, matrices: &[glam::Mat4]) -> Vec<u32> {
+        if matrices.is_empty() {
+            return Vec::new();
+        }
+
+        self.ensure_capacity(device, queue, self.current_slot + matrices.len());
+
+        let stride = self.uniform_size as usize;
+        let mut data = vec![0u8; stride * matrices.len()];
+        for (chunk, matrix) in data.chunks_exact_mut(stride).zip(matrices) {
+            chunk[..self.matrix_size as usize].copy_from_slice(bytemuck::cast_slice(matrix.as_ref()));
         }
-        
-        result
+
+        let base_offset = self.current_slot as u64 * self.uniform_size;
+        queue.write_buffer(&self.buffer, base_offset, &data);
+
+        let offsets = (0..matrices.len())
+            .map(|index| (base_offset + index as u64 * self.uniform_size) as u32)
+            .collect();
+        self.current_slot += matrices.len();
+
+        offsets
+    }
+}
+
+/// Capacity to grow to so that `required` slots fit, doubling to amortize reallocations
+pub(crate) fn grown_capacity(current: usize, required: usize) -> usize {
+    required.next_power_of_two().max(current * 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grown_capacity() {
+        assert_eq!(grown_capacity(64, 65), 128);
+        assert_eq!(grown_capacity(64, 1000), 1024);
+        assert_eq!(grown_capacity(1024, 1025), 2048);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_growing_keeps_earlier_slots() {
+        let (device, queue) = crate::renderer::test_device().expect("no GPU adapter available");
+        let mut uniforms = DynamicUniformBuffer::new(&device);
+        uniforms.reset_frame();
+
+        let first = glam::Mat4::from_translation(glam::Vec3::X);
+        let second = glam::Mat4::from_translation(glam::Vec3::Y);
+        let first_offsets = uniforms.upload_matrices(&device, &queue, &vec![first; INITIAL_CAPACITY - 1]);
+        let second_offsets = uniforms.upload_matrices(&device, &queue, &[second; 2]);
+
+        // The second upload grew the buffer, without moving or overwriting the first one's slots
+        assert!(uniforms.capacity() > INITIAL_CAPACITY);
+        assert!(second_offsets[0] > *first_offsets.last().unwrap());
+        let size = uniforms.capacity() as u64 * uniforms.uniform_size;
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Dynamic Uniform Readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&uniforms.buffer, 0, &readback, 0, size);
+        queue.submit(std::iter::once(encoder.finish()));
+        readback.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::PollType::Wait).unwrap();
+
+        let contents = readback.slice(..).get_mapped_range().to_vec();
+        let matrix_at = |offset: u32| {
+            let start = offset as usize;
+            glam::Mat4::from_cols_slice(bytemuck::cast_slice(&contents[start..start + 64]))
+        };
+        assert_eq!(matrix_at(first_offsets[0]), first);
+        assert_eq!(matrix_at(*first_offsets.last().unwrap()), first);
+        assert_eq!(matrix_at(second_offsets[1]), second);
     }
 }
@@ -0,0 +1,107 @@
+//! View-frustum culling
+//!
+//! Extracts the six clipping planes from a view-projection matrix so objects
+//! whose bounds lie entirely outside the view can be skipped before any GPU work.
+
+use glam::{Mat4, Vec3, Vec4};
+
+/// The region of space a camera can see, as six inward-facing planes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes as (normal, distance),
+    /// with points inside satisfying `normal.dot(point) + distance >= 0`
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract the planes of a view-projection matrix with wgpu's 0 to 1 depth range
+    pub fn from_view_projection(view_projection: Mat4) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|index| view_projection.row(index));
+        Self {
+            planes: [w + x, w - x, w + y, w - y, z, w - z],
+        }
+    }
+
+    /// Check whether a world-space axis-aligned box is at least partly inside
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        self.intersects_transformed_aabb(min, max, Mat4::IDENTITY)
+    }
+
+    /// Check whether a local-space axis-aligned box is at least partly inside once `model` is applied
+    ///
+    /// The test is conservative: boxes near a frustum corner may be kept even though they are out of view,
+    /// but a visible box is never rejected.
+    pub fn intersects_transformed_aabb(&self, min: Vec3, max: Vec3, model: Mat4) -> bool {
+        let (world_min, world_max) = transform_aabb(min, max, model);
+        let center = (world_min + world_max) * 0.5;
+        let world_half_extents = (world_max - world_min) * 0.5;
+
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            // How far the box reaches towards the plane's inside, in the plane's unnormalized units
+            let radius = world_half_extents.dot(normal.abs());
+            normal.dot(center) + plane.w + radius >= 0.0
+        })
+    }
+}
+
+/// Smallest axis-aligned box enclosing a local-space box once `model` is applied
+pub fn transform_aabb(min: Vec3, max: Vec3, model: Mat4) -> (Vec3, Vec3) {
+    // Transform the box's center, and grow its extents to enclose the rotated box
+    let center = model.transform_point3((min + max) * 0.5);
+    let half_extents = (max - min) * 0.5;
+    let world_half_extents = model.x_axis.truncate().abs() * half_extents.x
+        + model.y_axis.truncate().abs() * half_extents.y
+        + model.z_axis.truncate().abs() * half_extents.z;
+    (center - world_half_extents, center + world_half_extents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::camera::{Camera, ProjectionMode};
+
+    fn unit_box() -> (Vec3, Vec3) {
+        (Vec3::splat(-0.5), Vec3::splat(0.5))
+    }
+
+    #[test]
+    fn test_boxes_outside_the_view_are_rejected() {
+        let frustum = Frustum::from_view_projection(Camera::new(1.0).get_view_projection_matrix());
+        let (min, max) = unit_box();
+
+        assert!(frustum.intersects_aabb(min, max));
+        // Behind the camera, far to the side, and beyond the far plane
+        assert!(!frustum.intersects_aabb(min + Vec3::Z * 10.0, max + Vec3::Z * 10.0));
+        assert!(!frustum.intersects_aabb(min + Vec3::X * 50.0, max + Vec3::X * 50.0));
+        assert!(!frustum.intersects_aabb(min - Vec3::Z * 200.0, max - Vec3::Z * 200.0));
+        // Straddling the edge of the view still counts as visible
+        assert!(frustum.intersects_aabb(min + Vec3::X * 2.0, max + Vec3::X * 2.0));
+
+        let mut camera = Camera::new(1.0);
+        camera.set_projection_mode(ProjectionMode::Orthographic);
+        let frustum = Frustum::from_view_projection(camera.get_view_projection_matrix());
+        assert!(frustum.intersects_aabb(min, max));
+        assert!(!frustum.intersects_aabb(min + Vec3::Y * 3.0, max + Vec3::Y * 3.0));
+    }
+
+    #[test]
+    fn test_model_transform_is_applied() {
+        let frustum = Frustum::from_view_projection(Camera::new(1.0).get_view_projection_matrix());
+        let (min, max) = unit_box();
+
+        let moved_away = Mat4::from_translation(Vec3::X * 50.0);
+        assert!(!frustum.intersects_transformed_aabb(min, max, moved_away));
+
+        // Scaled up enough to reach back into view
+        let scaled = moved_away * Mat4::from_scale(Vec3::splat(100.0));
+        assert!(frustum.intersects_transformed_aabb(min, max, scaled));
+
+        // A long thin box rotated so it points into the view from the side
+        let (thin_min, thin_max) = (Vec3::new(-0.1, -0.1, -0.1), Vec3::new(20.0, 0.1, 0.1));
+        let pointing_away = Mat4::from_translation(Vec3::X * 5.0);
+        assert!(!frustum.intersects_transformed_aabb(thin_min, thin_max, pointing_away));
+        let pointing_in = pointing_away * Mat4::from_rotation_z(std::f32::consts::PI);
+        assert!(frustum.intersects_transformed_aabb(thin_min, thin_max, pointing_in));
+    }
+}
@@ -4,6 +4,8 @@
 
 use winit::window::Window;
 
+use crate::renderer::render_core::OPTIONAL_FEATURES;
+
 /// GPU initialization errors
 #[derive(Debug)]
 pub enum GpuError {
@@ -27,6 +29,7 @@ impl std::error::Error for GpuError {}
 /// Manages GPU device, surface, and core resources
 pub struct GpuContext {
     pub surface: wgpu::Surface<'static>,
+    pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
@@ -63,7 +66,8 @@ impl GpuContext {
 
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
+                // Unlocks the adapter's real MSAA sample counts (beyond the guaranteed 1 and 4)
+                required_features: adapter.features() & OPTIONAL_FEATURES,
                 required_limits: if cfg!(target_arch = "wasm32") {
                     wgpu::Limits::downlevel_webgl2_defaults()
                 } else {
@@ -121,6 +125,7 @@ impl GpuContext {
 
         Ok(Self {
             surface,
+            adapter,
             device,
             queue,
             config,
@@ -3,20 +3,32 @@
 //! Provides rendering capabilities without requiring a window system.
 //! Useful for embedding in existing applications or server-side rendering.
 
-use crate::renderable::{Cube, Quad, Triangle};
+use crate::renderable::{Circle, Cone, Cube, Cylinder, Quad, Sphere, Triangle};
 use crate::renderer::{
-    config::RenderConfig, 
-    render_core::RenderCore,
+    config::{BackgroundMode, HdrConfig, PostEffect, RenderConfig, RenderMode}, lighting::Lighting,
+    debug_draw::DebugDraw,
+    picking::PickResult,
+    shader::ShaderId,
+    skybox::EnvironmentId,
+    texture::{Texture, TextureId},
+    image::{Image, ImageFormat},
+    render_core::{RenderCore, RenderStats, OPTIONAL_FEATURES},
 };
+use crate::scene::Scene;
 use anyhow::Result;
+use std::path::Path;
 
 /// Headless renderer that renders to textures instead of windows
 pub struct HeadlessRenderer {
+    adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
     render_core: RenderCore,
     width: u32,
     height: u32,
+    #[allow(dead_code)]
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
 }
 
 impl HeadlessRenderer {
@@ -43,79 +55,109 @@ impl HeadlessRenderer {
 
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
+                // Unlocks the adapter's real MSAA sample counts (beyond the guaranteed 1 and 4)
+                required_features: adapter.features() & OPTIONAL_FEATURES,
                 required_limits: wgpu::Limits::default(),
                 ..Default::default()
             })
             .await?;
 
-        let render_core = RenderCore::new_headless(&device, width, height, config)?;
+        // Fall back to the best antialiasing the adapter supports instead of failing validation
+        let mut config = config;
+        let requested = config.antialiasing;
+        config.antialiasing = requested.downgrade_to_supported(&adapter, &device, config.scene_format(OUTPUT_FORMAT));
+        if config.antialiasing != requested {
+            log::warn!(
+                "Antialiasing mode {:?} is not supported by this adapter, using {:?}",
+                requested,
+                config.antialiasing
+            );
+        }
+
+        let render_core = RenderCore::new_headless(&device, &queue, width, height, config)?;
+        let (depth_texture, depth_view) = Self::create_depth_texture(&device, width, height);
 
         Ok(Self {
+            adapter,
             device,
             queue,
             render_core,
             width,
             height,
+            depth_texture,
+            depth_view,
         })
     }
 
-    /// Render scene to a texture (simplified version for demo)
-    /// Returns success/failure rather than pixel data for now
+    /// Render every entity in the scene and read the frame back
+    /// Returns tightly packed RGBA8 pixel data (row-major, top row first)
+    pub fn render_scene(&mut self, scene: &Scene) -> Result<Vec<u8>> {
+        let (triangles, quads, cubes, circles, cylinders, cones, spheres) = scene.get_all_renderables();
+        self.render_mixed_objects_to_buffer(&triangles, &quads, &cubes, &circles, &cylinders, &cones, &spheres)
+    }
+
+    /// Render every entity in the scene into an `Image`
+    pub fn render_scene_image(&mut self, scene: &Scene) -> Result<Image> {
+        let pixels = self.render_scene(scene)?;
+        Image::new(self.width, self.height, ImageFormat::Rgba8Srgb, pixels)
+    }
+
+    /// Render every entity in the scene and write the frame to disk
+    /// The file type (PNG or PPM) is chosen from the path's extension
+    pub fn render_to_file<P: AsRef<Path>>(&mut self, path: P, scene: &Scene) -> Result<()> {
+        self.render_scene_image(scene)?.save(path)
+    }
+
+    /// Render every entity in the scene and read back the linear HDR frame
+    ///
+    /// The values are exactly what objects drew into the HDR texture, before any
+    /// post effects or tonemapping, so they can exceed 1. Returns tightly packed RGBA
+    /// floats (row-major, top row first). Fails unless HDR is enabled in the config.
+    pub fn render_scene_hdr(&mut self, scene: &Scene) -> Result<Vec<f32>> {
+        if self.get_config().hdr.is_none() {
+            anyhow::bail!("HDR readback needs HDR rendering enabled in the render config");
+        }
+
+        let (triangles, quads, cubes, circles, cylinders, cones, spheres) = scene.get_all_renderables();
+        let (mut encoder, _) =
+            self.encode_mixed_objects(&triangles, &quads, &cubes, &circles, &cylinders, &cones, &spheres)?;
+        let scene_texture = self
+            .render_core
+            .post_processor
+            .scene_texture()
+            .expect("HDR frames are drawn into the post-processing scene texture");
+        let staging_buffer = self.copy_texture_to_staging(&mut encoder, scene_texture, HDR_BYTES_PER_PIXEL);
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let data = self.read_staging_buffer(&staging_buffer, HDR_BYTES_PER_PIXEL)?;
+        Ok(data
+            .chunks_exact(2)
+            .map(|half| f16_to_f32(u16::from_le_bytes([half[0], half[1]])))
+            .collect())
+    }
+
+    /// Find the entity under pixel (`x`, `y`) of the last frame, counted from the top-left corner
+    ///
+    /// Needs picking enabled with [`Self::set_picking`] or [`RenderConfig::picking`],
+    /// and resolves to `None` when no object with an entity id covers the pixel.
+    pub async fn pick(&self, x: u32, y: u32) -> Result<Option<PickResult>> {
+        Ok(self.render_core.pick(&self.device, &self.queue, x, y).await?)
+    }
+
+    /// Render triangles, quads and cubes to a texture and read the frame back
+    /// Kept for callers that own their objects; prefer `render_scene`
     pub fn render_to_buffer(
         &mut self,
         triangles: &[Triangle],
         quads: &[Quad],
         cubes: &[Cube],
     ) -> Result<Vec<u8>> {
-        // Create output texture
-        let texture_desc = wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.width,
-                height: self.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1, // Output texture is always single-sampled
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: Some("Headless Render Texture"),
-            view_formats: &[],
-        };
-        let output_texture = self.device.create_texture(&texture_desc);
-        let output_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        // Render the scene
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Headless Render Encoder"),
-            });
-
         // Convert to reference slices for RenderCore
         let triangle_refs: Vec<&Triangle> = triangles.iter().collect();
         let quad_refs: Vec<&Quad> = quads.iter().collect();
         let cube_refs: Vec<&Cube> = cubes.iter().collect();
 
-        // Use the shared render core
-        self.render_core.render_mixed_objects_core(
-            &self.device,
-            &self.queue,
-            &mut encoder,
-            &output_view,
-            None, // No multisampling in basic implementation
-            &triangle_refs,
-            &quad_refs,
-            &cube_refs,
-            true, // should_clear
-        )?;
-
-        self.queue.submit(std::iter::once(encoder.finish()));
-
-        // For now, return a dummy buffer indicating successful render
-        // In a real implementation, you'd copy the texture to a buffer and return the pixels
-        Ok(vec![0u8; (self.width * self.height * 4) as usize])
+        self.render_mixed_objects_to_buffer(&triangle_refs, &quad_refs, &cube_refs, &[], &[], &[], &[])
     }
 
     /// Get the camera for external manipulation
@@ -123,11 +165,81 @@ impl HeadlessRenderer {
         self.render_core.camera_mut()
     }
 
+    /// Upload tightly packed RGBA8 pixels (sRGB, top row first) as a texture for materials
+    pub fn load_texture(&mut self, width: u32, height: u32, data: &[u8]) -> Result<TextureId> {
+        Ok(self.render_core.textures.add_rgba8(&self.device, &self.queue, width, height, data)?)
+    }
+
+    /// Upload an image as a texture for materials
+    pub fn load_texture_image(&mut self, image: &Image) -> Result<TextureId> {
+        let texture = Texture::from_image(&self.device, &self.queue, image, Some("Texture"))?;
+        Ok(self.render_core.textures.add(&self.device, texture))
+    }
+
+    /// Release a texture; objects still using it are drawn untextured
+    pub fn remove_texture(&mut self, id: TextureId) -> bool {
+        self.render_core.textures.remove(id).is_some()
+    }
+
+    /// Upload six square faces (+X, -X, +Y, -Y, +Z, -Z) as a cubemap for `BackgroundMode::Environment`
+    pub fn load_cubemap(&mut self, faces: &[Image; 6]) -> Result<EnvironmentId> {
+        Ok(self.render_core.skybox.add_cubemap(&self.device, &self.queue, faces)?)
+    }
+
+    /// Upload an equirectangular panorama for `BackgroundMode::Environment`
+    pub fn load_panorama(&mut self, image: &Image) -> Result<EnvironmentId> {
+        Ok(self.render_core.skybox.add_panorama(&self.device, &self.queue, image)?)
+    }
+
+    /// Release a cubemap or panorama; a background still using it shows its clear color
+    pub fn remove_environment(&mut self, id: EnvironmentId) -> bool {
+        self.render_core.skybox.remove(id)
+    }
+
+    /// Compile a WGSL shader under a name for materials to select
+    ///
+    /// Registering an existing name replaces that shader. See
+    /// [`crate::renderer::shader`] for the inputs and bindings a shader can use.
+    /// Resolves once wgpu has checked the shader's pipelines.
+    pub async fn register_shader(&mut self, name: &str, source: &str) -> Result<ShaderId> {
+        Ok(self.render_core.pipeline.register_shader(&self.device, name, source).await?)
+    }
+
+    /// Look up a registered shader by name
+    pub fn shader_id(&self, name: &str) -> Option<ShaderId> {
+        self.render_core.pipeline.cache.shaders().id(name)
+    }
+
+    /// Get the scene's lights
+    pub fn lighting(&self) -> &Lighting {
+        self.render_core.lighting()
+    }
+
+    /// Get mutable lights for adding, removing or adjusting them
+    pub fn lighting_mut(&mut self) -> &mut Lighting {
+        self.render_core.lighting_mut()
+    }
+
+    /// Get the debug lines queued for the next frame
+    pub fn debug_draw(&self) -> &DebugDraw {
+        self.render_core.debug_draw()
+    }
+
+    /// Get mutable debug lines, for queueing lines, arrows, grids, axes and boxes drawn by the next render
+    pub fn debug_draw_mut(&mut self) -> &mut DebugDraw {
+        self.render_core.debug_draw_mut()
+    }
+
     /// Resize the render target
     pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
         self.width = width;
         self.height = height;
-        self.render_core.resize(width, height);
+        self.render_core.resize(&self.device, width, height);
+
+        // Recreate depth texture with new size
+        let (depth_texture, depth_view) = Self::create_depth_texture(&self.device, width, height);
+        self.depth_texture = depth_texture;
+        self.depth_view = depth_view;
         Ok(())
     }
 
@@ -136,16 +248,23 @@ impl HeadlessRenderer {
         (self.width, self.height)
     }
 
-    /// Get access to the underlying device for capability queries
+    /// Get access to the underlying device
     pub fn device(&self) -> &wgpu::Device {
         &self.device
     }
 
+    /// Get access to the adapter for capability queries
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        &self.adapter
+    }
+
     /// Update the rendering configuration (antialiasing, culling, etc.)
+    /// Fails with `ConfigError::UnsupportedAntialiasing` if the adapter can't use the requested MSAA mode
     pub fn update_config(&mut self, config: RenderConfig) -> Result<()> {
+        config.antialiasing.validate(&self.adapter, &self.device, config.scene_format(OUTPUT_FORMAT))?;
         self.render_core.update_config(
             &self.device,
-            wgpu::TextureFormat::Rgba8UnormSrgb,
+            OUTPUT_FORMAT,
             config,
             self.width,
             self.height,
@@ -158,6 +277,17 @@ impl HeadlessRenderer {
         self.render_core.get_config()
     }
 
+    /// Get what the most recent render submitted, culled and drew
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_core.render_stats()
+    }
+
+    /// Get the antialiasing modes the adapter supports for the format objects are drawn into
+    pub fn supported_antialiasing_modes(&self) -> Vec<crate::renderer::config::AntialiasingMode> {
+        let format = self.get_config().scene_format(OUTPUT_FORMAT);
+        crate::renderer::config::AntialiasingMode::get_supported_modes(&self.adapter, &self.device, format)
+    }
+
     /// Set antialiasing mode specifically
     pub fn set_antialiasing(
         &mut self,
@@ -182,29 +312,107 @@ impl HeadlessRenderer {
         self.update_config(config)
     }
 
-    /// Switch to 2D optimized settings (no backface culling, alpha blending)
+    /// Set the background (solid color, transparent, or gradient)
+    pub fn set_background(&mut self, background: BackgroundMode) -> Result<()> {
+        let mut config = self.render_core.get_config().clone();
+        config.background = background;
+        self.update_config(config)
+    }
+
+    /// Replace the post-processing chain; an empty chain renders straight to the output
+    pub fn set_post_processing(&mut self, effects: Vec<PostEffect>) -> Result<()> {
+        let mut config = self.render_core.get_config().clone();
+        config.post_processing = effects;
+        self.update_config(config)
+    }
+
+    /// Enable HDR rendering with the given tonemapping, or disable it with `None`
+    pub fn set_hdr(&mut self, hdr: Option<HdrConfig>) -> Result<()> {
+        let mut config = self.render_core.get_config().clone();
+        config.hdr = hdr;
+        self.update_config(config)
+    }
+
+    /// Enable or disable the ID buffer used by [`Self::pick`]
+    pub fn set_picking(&mut self, enabled: bool) -> Result<()> {
+        let mut config = self.render_core.get_config().clone();
+        config.picking = enabled;
+        self.update_config(config)
+    }
+
+    /// Draw objects shaded, or in one of the debug views
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) -> Result<()> {
+        let mut config = self.render_core.get_config().clone();
+        config.render_mode = render_mode;
+        self.update_config(config)
+    }
+
+    /// Carry the current background, post-processing, HDR, picking and render mode settings over to a preset config
+    fn keep_output_settings(&self, preset: RenderConfig) -> RenderConfig {
+        let current = self.get_config();
+        RenderConfig {
+            hdr: current.hdr,
+            picking: current.picking,
+            render_mode: current.render_mode,
+            ..preset
+                .with_background(current.background)
+                .with_post_processing(current.post_processing.clone())
+        }
+    }
+
+    /// Switch to 2D optimized settings (no backface culling, alpha blending), keeping the background and effects
     pub fn set_2d_mode(&mut self) -> Result<()> {
-        self.update_config(RenderConfig::for_2d())
+        self.update_config(self.keep_output_settings(RenderConfig::for_2d()))
     }
 
-    /// Switch to 3D optimized settings (backface culling, no alpha blending)
+    /// Switch to 3D optimized settings (backface culling, no alpha blending), keeping the background and effects
     pub fn set_3d_mode(&mut self) -> Result<()> {
-        self.update_config(RenderConfig::for_3d())
+        self.update_config(self.keep_output_settings(RenderConfig::for_3d()))
     }
 
-    /// Switch to performance mode (no antialiasing)
+    /// Switch to performance mode (no antialiasing), keeping the background and effects
     pub fn set_performance_mode(&mut self) -> Result<()> {
-        self.update_config(RenderConfig::performance())
+        self.update_config(self.keep_output_settings(RenderConfig::performance()))
     }
 
-    /// Render mixed object types with per-object culling support
-    /// Objects are grouped by culling mode and rendered in separate passes
+    /// Render mixed object types (all primitive types) with per-object culling support
+    /// Objects are grouped by culling mode and rendered in separate passes to the same frame
+    #[allow(clippy::too_many_arguments)]
     pub fn render_mixed_objects_to_buffer(
         &mut self,
         triangles: &[&Triangle],
         quads: &[&Quad],
         cubes: &[&Cube],
+        circles: &[&Circle],
+        cylinders: &[&Cylinder],
+        cones: &[&Cone],
+        spheres: &[&Sphere],
     ) -> Result<Vec<u8>> {
+        let (mut encoder, output_texture) =
+            self.encode_mixed_objects(triangles, quads, cubes, circles, cylinders, cones, spheres)?;
+
+        let staging_buffer = self.copy_texture_to_staging(&mut encoder, &output_texture, BYTES_PER_PIXEL);
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let total_objects = triangles.len() + quads.len() + cubes.len() + circles.len()
+            + cylinders.len() + cones.len() + spheres.len();
+        log::debug!("Headless render: {} total objects", total_objects);
+
+        self.read_staging_buffer(&staging_buffer, BYTES_PER_PIXEL)
+    }
+
+    /// Record a frame into a new output texture, returning the unsubmitted encoder and the texture
+    #[allow(clippy::too_many_arguments)]
+    fn encode_mixed_objects(
+        &mut self,
+        triangles: &[&Triangle],
+        quads: &[&Quad],
+        cubes: &[&Cube],
+        circles: &[&Circle],
+        cylinders: &[&Cylinder],
+        cones: &[&Cone],
+        spheres: &[&Sphere],
+    ) -> Result<(wgpu::CommandEncoder, wgpu::Texture)> {
         // Create output texture
         let texture_desc = wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
@@ -213,11 +421,12 @@ impl HeadlessRenderer {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count: 1, // Output texture is always single-sampled
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: Some("Headless Mixed Objects Texture"),
+            format: OUTPUT_FORMAT,
+            // COPY_SRC so the finished frame can be copied into a staging buffer
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            label: Some("Headless Render Texture"),
             view_formats: &[],
         };
         let output_texture = self.device.create_texture(&texture_desc);
@@ -227,7 +436,7 @@ impl HeadlessRenderer {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Headless Mixed Objects Encoder"),
+                label: Some("Headless Render Encoder"),
             });
 
         // Use the shared render core for actual per-object culling rendering
@@ -236,20 +445,191 @@ impl HeadlessRenderer {
             &self.queue,
             &mut encoder,
             &output_view,
-            None, // No multisampling in basic implementation
+            &self.depth_view,
+            None, // Will be handled inside the core based on config
             triangles,
             quads,
             cubes,
+            circles,
+            cylinders,
+            cones,
+            spheres,
             true, // should_clear
         )?;
 
-        self.queue.submit(std::iter::once(encoder.finish()));
+        Ok((encoder, output_texture))
+    }
+
+    /// Create a depth texture matching the render target size
+    fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Depth Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    /// Record a copy of the rendered texture into a mappable staging buffer
+    /// Rows in the staging buffer are padded to wgpu's 256-byte copy alignment
+    fn copy_texture_to_staging(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        bytes_per_pixel: u32,
+    ) -> wgpu::Buffer {
+        let padded_bytes_per_row = padded_bytes_per_row(self.width, bytes_per_pixel);
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Readback Buffer"),
+            size: (padded_bytes_per_row * self.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        staging_buffer
+    }
+
+    /// Map a staging buffer filled by `copy_texture_to_staging` and strip the row padding
+    /// Blocks until the GPU has finished the submitted work
+    fn read_staging_buffer(&self, staging_buffer: &wgpu::Buffer, bytes_per_pixel: u32) -> Result<Vec<u8>> {
+        let buffer_slice = staging_buffer.slice(..);
+        let (sender, mut receiver) = futures_channel::oneshot::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        self.device.poll(wgpu::PollType::Wait)?;
+
+        match receiver.try_recv() {
+            Ok(Some(result)) => result?,
+            _ => anyhow::bail!("Headless readback buffer was not mapped after device poll"),
+        }
+
+        let pixels = {
+            let padded_data = buffer_slice.get_mapped_range();
+            unpad_rows(&padded_data, self.width, self.height, bytes_per_pixel)
+        };
+        staging_buffer.unmap();
+
+        Ok(pixels)
+    }
+}
+
+/// Format of the headless output texture
+const OUTPUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Bytes per pixel of the RGBA8 output texture
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// Bytes per pixel of the RGBA16 float scene texture used for HDR rendering
+const HDR_BYTES_PER_PIXEL: u32 = 8;
+
+/// Row pitch of a texture-to-buffer copy, rounded up to `COPY_BYTES_PER_ROW_ALIGNMENT`
+fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let unpadded = width * bytes_per_pixel;
+    unpadded.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+/// Remove per-row padding, returning tightly packed pixel data
+fn unpad_rows(padded_data: &[u8], width: u32, height: u32, bytes_per_pixel: u32) -> Vec<u8> {
+    let unpadded_bytes_per_row = (width * bytes_per_pixel) as usize;
+    let padded_bytes_per_row = padded_bytes_per_row(width, bytes_per_pixel) as usize;
+
+    let mut pixels = Vec::with_capacity(unpadded_bytes_per_row * height as usize);
+    for row in padded_data.chunks(padded_bytes_per_row).take(height as usize) {
+        pixels.extend_from_slice(&row[..unpadded_bytes_per_row]);
+    }
+    pixels
+}
+
+/// Widen an IEEE 754 half-precision float, which is exact for every value
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_bytes_per_row_alignment() {
+        // 64 pixels * 4 bytes is already aligned
+        assert_eq!(padded_bytes_per_row(64, BYTES_PER_PIXEL), 256);
+        // Anything else rounds up to the next multiple of 256
+        assert_eq!(padded_bytes_per_row(1, BYTES_PER_PIXEL), 256);
+        assert_eq!(padded_bytes_per_row(65, BYTES_PER_PIXEL), 512);
+        assert_eq!(padded_bytes_per_row(800, BYTES_PER_PIXEL), 3328);
+        // HDR pixels are twice as wide
+        assert_eq!(padded_bytes_per_row(64, HDR_BYTES_PER_PIXEL), 512);
+    }
+
+    #[test]
+    fn test_unpad_rows_strips_padding() {
+        // 3x2 image: each row is 12 bytes of data followed by 244 bytes of padding
+        let width = 3;
+        let height = 2;
+        let padded = padded_bytes_per_row(width, BYTES_PER_PIXEL) as usize;
+        let mut data = vec![0xAAu8; padded * height as usize];
+        for row in 0..height as usize {
+            for byte in 0..12 {
+                data[row * padded + byte] = (row * 12 + byte) as u8;
+            }
+        }
+
+        let pixels = unpad_rows(&data, width, height, BYTES_PER_PIXEL);
+        assert_eq!(pixels.len(), 24);
+        assert_eq!(pixels, (0..24).collect::<Vec<u8>>());
+    }
 
-        // Log completion
-        let total_objects = triangles.len() + quads.len() + cubes.len();
-        log::info!("✅ Headless per-object culling render: {} total objects", total_objects);
-        
-        // Return placeholder pixel data
-        Ok(vec![0u8; (self.width * self.height * 4) as usize])
+    #[test]
+    fn test_f16_to_f32() {
+        assert_eq!(f16_to_f32(0x0000), 0.0);
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x3555), 0.333_251_95);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+        assert!(f16_to_f32(0x7e00).is_nan());
     }
 }
@@ -0,0 +1,250 @@
+//! CPU-side images and file encoders
+//!
+//! Holds pixel data read back from the GPU and writes it to disk as PNG
+//! or binary PPM (a dependency-free fallback that drops the alpha channel).
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+/// Pixel layout of an `Image`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// 8-bit RGBA with sRGB-encoded color channels (matches `Rgba8UnormSrgb` textures)
+    Rgba8Srgb,
+    /// 8-bit RGBA with linear color channels (matches `Rgba8Unorm` textures)
+    Rgba8Linear,
+}
+
+impl ImageFormat {
+    /// Bytes used by a single pixel
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            ImageFormat::Rgba8Srgb | ImageFormat::Rgba8Linear => 4,
+        }
+    }
+
+    /// Map a wgpu texture format to the matching image format, if there is one
+    pub fn from_texture_format(format: wgpu::TextureFormat) -> Option<Self> {
+        match format {
+            wgpu::TextureFormat::Rgba8UnormSrgb => Some(ImageFormat::Rgba8Srgb),
+            wgpu::TextureFormat::Rgba8Unorm => Some(ImageFormat::Rgba8Linear),
+            _ => None,
+        }
+    }
+}
+
+/// File container used when saving an `Image`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFileType {
+    /// PNG with color space metadata
+    Png,
+    /// Binary PPM (P6), RGB only
+    Ppm,
+}
+
+impl ImageFileType {
+    /// Pick a file type from a path's extension (case-insensitive)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ImageFileType::Png),
+            "ppm" => Some(ImageFileType::Ppm),
+            _ => None,
+        }
+    }
+}
+
+/// Tightly packed image stored row-major, top row first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    data: Vec<u8>,
+}
+
+impl Image {
+    /// Wrap existing pixel data, checking that its size matches the dimensions
+    pub fn new(width: u32, height: u32, format: ImageFormat, data: Vec<u8>) -> Result<Self> {
+        let expected = width as usize * height as usize * format.bytes_per_pixel();
+        if data.len() != expected {
+            anyhow::bail!(
+                "Image data is {} bytes but {}x{} {:?} needs {} bytes",
+                data.len(),
+                width,
+                height,
+                format,
+                expected
+            );
+        }
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            data,
+        })
+    }
+
+    /// Image width in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Image height in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixel layout of the image data
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Raw pixel data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consume the image and return its pixel data
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Get the RGBA value of a single pixel
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y as usize * self.width as usize + x as usize) * self.format.bytes_per_pixel();
+        let mut pixel = [0u8; 4];
+        pixel.copy_from_slice(&self.data[offset..offset + 4]);
+        Some(pixel)
+    }
+
+    /// Save the image, choosing PNG or PPM from the file extension
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file_type = ImageFileType::from_path(path).with_context(|| {
+            format!("Unsupported image extension for {} (expected .png or .ppm)", path.display())
+        })?;
+        self.save_as(path, file_type)
+    }
+
+    /// Save the image with an explicit file type, ignoring the extension
+    pub fn save_as<P: AsRef<Path>>(&self, path: P, file_type: ImageFileType) -> Result<()> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create image file {}", path.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        match file_type {
+            ImageFileType::Png => self.write_png(&mut writer)?,
+            ImageFileType::Ppm => self.write_ppm(&mut writer)?,
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Encode the image as PNG, tagging the color space to match the pixel format
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        match self.format {
+            ImageFormat::Rgba8Srgb => {
+                // sRGB chunk plus the gAMA/cHRM fallback values from the PNG spec (11.3.2.5)
+                encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+                encoder.set_source_gamma(png::ScaledFloat::from_scaled(45455));
+                encoder.set_source_chromaticities(png::SourceChromaticities {
+                    white: (png::ScaledFloat::from_scaled(31270), png::ScaledFloat::from_scaled(32900)),
+                    red: (png::ScaledFloat::from_scaled(64000), png::ScaledFloat::from_scaled(33000)),
+                    green: (png::ScaledFloat::from_scaled(30000), png::ScaledFloat::from_scaled(60000)),
+                    blue: (png::ScaledFloat::from_scaled(15000), png::ScaledFloat::from_scaled(6000)),
+                });
+            }
+            ImageFormat::Rgba8Linear => encoder.set_source_gamma(png::ScaledFloat::new(1.0)),
+        }
+
+        let mut png_writer = encoder.write_header()?;
+        png_writer.write_image_data(&self.data)?;
+        png_writer.finish()?;
+        Ok(())
+    }
+
+    /// Encode the image as binary PPM (P6); alpha is discarded
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+        let rgb: Vec<u8> = self
+            .data
+            .chunks_exact(self.format.bytes_per_pixel())
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect();
+        writer.write_all(&rgb)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_image() -> Image {
+        // 2x1 image: one opaque red pixel, one half-transparent blue pixel
+        Image::new(2, 1, ImageFormat::Rgba8Srgb, vec![255, 0, 0, 255, 0, 0, 255, 128]).unwrap()
+    }
+
+    #[test]
+    fn test_image_rejects_wrong_data_size() {
+        assert!(Image::new(2, 2, ImageFormat::Rgba8Srgb, vec![0; 15]).is_err());
+        assert!(Image::new(2, 2, ImageFormat::Rgba8Srgb, vec![0; 16]).is_ok());
+    }
+
+    #[test]
+    fn test_image_pixel_access() {
+        let image = test_image();
+        assert_eq!(image.pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(image.pixel(1, 0), Some([0, 0, 255, 128]));
+        assert_eq!(image.pixel(2, 0), None);
+    }
+
+    #[test]
+    fn test_file_type_from_extension() {
+        assert_eq!(ImageFileType::from_path(Path::new("frame.png")), Some(ImageFileType::Png));
+        assert_eq!(ImageFileType::from_path(Path::new("frame.PPM")), Some(ImageFileType::Ppm));
+        assert_eq!(ImageFileType::from_path(Path::new("frame.jpg")), None);
+        assert_eq!(ImageFileType::from_path(Path::new("frame")), None);
+    }
+
+    #[test]
+    fn test_ppm_encoding() {
+        let mut bytes = Vec::new();
+        test_image().write_ppm(&mut bytes).unwrap();
+
+        let header = b"P6\n2 1\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], &[255, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_png_round_trip_with_srgb_metadata() {
+        let image = test_image();
+        let mut bytes = Vec::new();
+        image.write_png(&mut bytes).unwrap();
+
+        let decoder = png::Decoder::new(bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        assert!(reader.info().srgb.is_some());
+        assert_eq!(reader.info().source_gamma, Some(png::ScaledFloat::from_scaled(45455)));
+
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut decoded).unwrap();
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_eq!(frame.color_type, png::ColorType::Rgba);
+        assert_eq!(&decoded[..frame.buffer_size()], image.data());
+    }
+}
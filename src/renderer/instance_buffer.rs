@@ -0,0 +1,211 @@
+//! Per-instance data for instanced drawing
+//!
+//! Objects that share a mesh are drawn with a single instanced draw call. Each
+//! instance reads its model matrix and material from a vertex buffer stepped
+//! per instance, which grows on demand like the dynamic uniform buffer.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+use std::ops::Range;
+
+use crate::renderable::Material;
+use crate::scene::EntityId;
+use crate::renderer::dynamic_uniforms::grown_capacity;
+
+/// Number of instances allocated up front, before any growth
+const INITIAL_CAPACITY: usize = 1024;
+
+/// First shader location used by instance attributes, leaving the lower ones for per-vertex data
+pub const INSTANCE_LOCATION_START: u32 = 8;
+
+/// Per-instance vertex data
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceData {
+    pub model: [[f32; 4]; 4],
+    /// Material base color, with the opacity in `w`
+    pub base_color: [f32; 4],
+    /// Material emissive color, with the roughness in `w`
+    pub emissive: [f32; 4],
+    /// 1 in `x` when the base color is multiplied by the vertex colors,
+    /// 1 in `y` when the object receives shadows
+    pub flags: [f32; 4],
+    /// Entity id plus one, written to the ID buffer for picking; 0 when the object has no id
+    pub pick_id: u32,
+}
+
+impl InstanceData {
+    pub fn new(model: glam::Mat4, material: &Material) -> Self {
+        let [r, g, b] = material.base_color;
+        let [er, eg, eb] = material.emissive;
+        Self {
+            model: model.to_cols_array_2d(),
+            base_color: [r, g, b, material.opacity],
+            emissive: [er, eg, eb, material.roughness],
+            flags: [
+                if material.use_vertex_colors { 1.0 } else { 0.0 },
+                if material.receives_shadows { 1.0 } else { 0.0 },
+                0.0,
+                0.0,
+            ],
+            pick_id: 0,
+        }
+    }
+
+    /// Report `entity_id` when this instance is picked
+    pub fn with_entity_id(mut self, entity_id: Option<EntityId>) -> Self {
+        self.pick_id = entity_id.map_or(0, |id| id + 1);
+        self
+    }
+
+    /// Instance with the default material
+    pub fn from_matrix(model: glam::Mat4) -> Self {
+        Self::new(model, &Material::default())
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+            INSTANCE_LOCATION_START => Float32x4,
+            INSTANCE_LOCATION_START + 1 => Float32x4,
+            INSTANCE_LOCATION_START + 2 => Float32x4,
+            INSTANCE_LOCATION_START + 3 => Float32x4,
+            INSTANCE_LOCATION_START + 4 => Float32x4,
+            INSTANCE_LOCATION_START + 5 => Float32x4,
+            INSTANCE_LOCATION_START + 6 => Float32x4,
+            INSTANCE_LOCATION_START + 7 => Uint32,
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<InstanceData>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Growable vertex buffer holding the instances of one frame
+pub struct InstanceBuffer {
+    buffer: wgpu::Buffer,
+    capacity: usize,
+}
+
+impl InstanceBuffer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            buffer: Self::create_buffer(device, INITIAL_CAPACITY),
+            capacity: INITIAL_CAPACITY,
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (mem::size_of::<InstanceData>() * capacity) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Upload the instances for a frame, reallocating if they don't fit
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[InstanceData]) {
+        if instances.len() > self.capacity {
+            let capacity = grown_capacity(self.capacity, instances.len());
+            log::debug!("Growing instance buffer from {} to {} instances", self.capacity, capacity);
+            self.buffer = Self::create_buffer(device, capacity);
+            self.capacity = capacity;
+        }
+
+        if !instances.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        }
+    }
+
+    /// Get the buffer to bind as the instance vertex buffer
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Number of instances the buffer can currently hold
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// Group item indices by key, keeping keys in order of first appearance
+pub fn batch_by_key<K: Copy + Eq + Hash>(keys: &[K]) -> Vec<(K, Vec<usize>)> {
+    let mut batches: Vec<(K, Vec<usize>)> = Vec::new();
+    let mut batch_index: HashMap<K, usize> = HashMap::new();
+
+    for (index, key) in keys.iter().enumerate() {
+        let slot = *batch_index.entry(*key).or_insert_with(|| {
+            batches.push((*key, Vec::new()));
+            batches.len() - 1
+        });
+        batches[slot].1.push(index);
+    }
+
+    batches
+}
+
+/// Group runs of consecutive items sharing a key, keeping the items' order
+pub fn batch_consecutive<K: Copy + Eq>(keys: &[K]) -> Vec<(K, Range<usize>)> {
+    let mut batches: Vec<(K, Range<usize>)> = Vec::new();
+    for (index, key) in keys.iter().enumerate() {
+        match batches.last_mut() {
+            Some((last_key, range)) if last_key == key => range.end = index + 1,
+            _ => batches.push((*key, index..index + 1)),
+        }
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_by_key_groups_in_first_seen_order() {
+        let batches = batch_by_key(&['b', 'a', 'b', 'c', 'a', 'b']);
+        assert_eq!(
+            batches,
+            vec![('b', vec![0, 2, 5]), ('a', vec![1, 4]), ('c', vec![3])]
+        );
+    }
+
+    #[test]
+    fn test_batch_consecutive_keeps_order() {
+        let batches = batch_consecutive(&['b', 'b', 'a', 'b', 'c', 'c']);
+        assert_eq!(batches, vec![('b', 0..2), ('a', 2..3), ('b', 3..4), ('c', 4..6)]);
+        assert!(batch_consecutive::<char>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_instance_layout_matches_data() {
+        let layout = InstanceData::desc();
+        assert_eq!(layout.array_stride, 116);
+        assert_eq!(layout.step_mode, wgpu::VertexStepMode::Instance);
+        assert_eq!(layout.attributes.len(), 8);
+        assert_eq!(layout.attributes[3].offset, 48);
+        assert_eq!(layout.attributes[6].offset, 96);
+        assert_eq!(layout.attributes[7].offset, 112);
+
+        let data = InstanceData::from_matrix(glam::Mat4::from_translation(glam::Vec3::new(1.0, 2.0, 3.0)));
+        assert_eq!(data.model[3], [1.0, 2.0, 3.0, 1.0]);
+        assert_eq!(data.base_color, [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(data.flags[0], 1.0);
+        assert_eq!(data.flags[1], 1.0);
+        assert_eq!(data.pick_id, 0);
+        assert_eq!(data.with_entity_id(Some(0)).pick_id, 1);
+
+        let material = Material::new([0.5, 0.0, 0.0])
+            .with_opacity(0.25)
+            .with_roughness(0.8)
+            .with_receive_shadows(false);
+        let data = InstanceData::new(glam::Mat4::IDENTITY, &material);
+        assert_eq!(data.base_color, [0.5, 0.0, 0.0, 0.25]);
+        assert_eq!(data.emissive[3], 0.8);
+        assert_eq!(data.flags[0], 0.0);
+        assert_eq!(data.flags[1], 0.0);
+    }
+}
@@ -0,0 +1,432 @@
+//! Scene lighting
+//!
+//! Lights are described on the CPU by [`Lighting`] and packed into a single
+//! uniform block each frame. The basic shader shades every fragment with an
+//! ambient term, an optional directional light and up to [`MAX_POINT_LIGHTS`]
+//! point lights, using Lambert diffuse and Blinn-Phong specular. The
+//! directional light can cast shadows, looked up in a shadow map drawn by
+//! [`ShadowRenderer`](crate::renderer::shadow::ShadowRenderer).
+
+use std::mem;
+
+use glam::{Mat4, Vec3};
+
+/// Number of point lights the shader evaluates; extra lights are ignored
+pub const MAX_POINT_LIGHTS: usize = 8;
+
+/// Shadow map settings for the directional light
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowSettings {
+    /// Width and height of the shadow map in texels
+    pub resolution: u32,
+    /// Offset subtracted from a surface's depth before it's compared with the shadow map,
+    /// in the shadow map's 0 to 1 depth range; raise it if lit surfaces show striped self-shadowing
+    pub depth_bias: f32,
+    /// Texels filtered in each direction around a lookup; 0 gives hard edges
+    pub pcf_radius: u32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            depth_bias: 0.001,
+            pcf_radius: 1,
+        }
+    }
+}
+
+/// A light infinitely far away, such as the sun
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// Direction the light travels in, from the light toward the scene
+    pub direction: Vec3,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Shadows cast by objects onto each other, `None` to light everything
+    pub shadows: Option<ShadowSettings>,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, color: [f32; 3], intensity: f32) -> Self {
+        Self {
+            direction,
+            color,
+            intensity,
+            shadows: None,
+        }
+    }
+
+    /// Enable shadows for this light
+    pub fn with_shadows(mut self, settings: ShadowSettings) -> Self {
+        self.shadows = Some(settings);
+        self
+    }
+}
+
+/// A light radiating from a point, fading out to nothing at `range`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: Vec3,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Distance at which the light no longer contributes
+    pub range: f32,
+}
+
+impl PointLight {
+    pub fn new(position: Vec3, color: [f32; 3], intensity: f32, range: f32) -> Self {
+        Self {
+            position,
+            color,
+            intensity,
+            range,
+        }
+    }
+}
+
+/// Every light in the scene, along with the shared specular settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lighting {
+    pub ambient_color: [f32; 3],
+    pub ambient_intensity: f32,
+    pub directional: Option<DirectionalLight>,
+    /// Point lights; only the first [`MAX_POINT_LIGHTS`] are used
+    pub point_lights: Vec<PointLight>,
+    /// Scale applied to specular highlights, 0 disables them
+    ///
+    /// Applies as-is to materials with the default roughness of 0.5; smoother
+    /// materials get stronger highlights and rougher ones weaker.
+    pub specular_strength: f32,
+    /// Blinn-Phong exponent; higher values give smaller, sharper highlights
+    ///
+    /// Scaled by material roughness the same way as `specular_strength`.
+    pub shininess: f32,
+}
+
+impl Default for Lighting {
+    /// Soft ambient light plus a white key light from the upper left, in front of the scene
+    fn default() -> Self {
+        Self {
+            ambient_color: [1.0, 1.0, 1.0],
+            ambient_intensity: 0.25,
+            directional: Some(DirectionalLight::new(Vec3::new(-0.3, -0.5, -1.0), [1.0, 1.0, 1.0], 0.8)),
+            point_lights: Vec::new(),
+            specular_strength: 0.5,
+            shininess: 32.0,
+        }
+    }
+}
+
+impl Lighting {
+    /// Full ambient light and nothing else, so objects show their unshaded colors
+    pub fn unlit() -> Self {
+        Self {
+            ambient_color: [1.0, 1.0, 1.0],
+            ambient_intensity: 1.0,
+            directional: None,
+            point_lights: Vec::new(),
+            specular_strength: 0.0,
+            shininess: 32.0,
+        }
+    }
+
+    /// Set the ambient light
+    pub fn with_ambient(mut self, color: [f32; 3], intensity: f32) -> Self {
+        self.ambient_color = color;
+        self.ambient_intensity = intensity;
+        self
+    }
+
+    /// Set or remove the directional light
+    pub fn with_directional(mut self, directional: Option<DirectionalLight>) -> Self {
+        self.directional = directional;
+        self
+    }
+
+    /// Add a point light
+    pub fn add_point_light(&mut self, light: PointLight) {
+        if self.point_lights.len() >= MAX_POINT_LIGHTS {
+            log::warn!(
+                "Only {} point lights are supported, the extra light will be ignored",
+                MAX_POINT_LIGHTS
+            );
+        }
+        self.point_lights.push(light);
+    }
+
+    /// Shadow settings of the directional light, if it has shadows enabled
+    pub fn shadow_settings(&self) -> Option<ShadowSettings> {
+        self.directional.and_then(|light| light.shadows)
+    }
+
+    /// Pack the lights into the shader's uniform layout
+    fn to_uniform(&self, camera_position: Vec3) -> LightUniform {
+        let scaled = |color: [f32; 3], intensity: f32| {
+            [color[0] * intensity, color[1] * intensity, color[2] * intensity, 0.0]
+        };
+
+        let (directional_direction, directional_color) = match self.directional {
+            Some(light) => {
+                let direction = light.direction.normalize_or_zero();
+                ([direction.x, direction.y, direction.z, 1.0], scaled(light.color, light.intensity))
+            }
+            None => ([0.0; 4], [0.0; 4]),
+        };
+
+        let mut point_lights = [<PointLightUniform as bytemuck::Zeroable>::zeroed(); MAX_POINT_LIGHTS];
+        let point_light_count = self.point_lights.len().min(MAX_POINT_LIGHTS);
+        for (slot, light) in point_lights.iter_mut().zip(&self.point_lights) {
+            *slot = PointLightUniform {
+                position_range: [light.position.x, light.position.y, light.position.z, light.range],
+                color: scaled(light.color, light.intensity),
+            };
+        }
+
+        LightUniform {
+            ambient: scaled(self.ambient_color, self.ambient_intensity),
+            directional_direction,
+            directional_color,
+            camera_position: [camera_position.x, camera_position.y, camera_position.z, 1.0],
+            specular: [self.specular_strength, self.shininess, 0.0, 0.0],
+            point_light_count: point_light_count as u32,
+            time: 0.0,
+            _padding: [0; 2],
+            point_lights,
+            shadow_view_proj: Mat4::IDENTITY.to_cols_array_2d(),
+            shadow: [0.0; 4],
+        }
+    }
+}
+
+/// A point light as laid out in the shader's uniform block
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PointLightUniform {
+    position_range: [f32; 4],
+    color: [f32; 4],
+}
+
+/// Lights as laid out in the shader's uniform block
+///
+/// Colors are premultiplied by their intensity. The directional light's `w`
+/// component is 1 when the light is enabled.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightUniform {
+    ambient: [f32; 4],
+    directional_direction: [f32; 4],
+    directional_color: [f32; 4],
+    camera_position: [f32; 4],
+    /// Specular strength and shininess
+    specular: [f32; 4],
+    point_light_count: u32,
+    /// Seconds since the renderer started, shared with custom shaders
+    time: f32,
+    _padding: [u32; 2],
+    point_lights: [PointLightUniform; MAX_POINT_LIGHTS],
+    /// The directional light's view-projection, mapping world positions into the shadow map
+    shadow_view_proj: [[f32; 4]; 4],
+    /// 1 in `x` when shadows are enabled, then the depth bias, the size of a
+    /// shadow map texel in UV units and the PCF radius
+    shadow: [f32; 4],
+}
+
+/// Size of the light block bound at group 1, binding 0
+pub(crate) const LIGHT_UNIFORM_SIZE: u64 = mem::size_of::<LightUniform>() as u64;
+
+/// GPU copy of the scene's lights, bound at group 1 of the basic shader
+/// along with the directional light's shadow map
+pub struct LightBuffer {
+    buffer: wgpu::Buffer,
+    shadow_sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+impl LightBuffer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Light Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+        });
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light Uniform Buffer"),
+            size: mem::size_of::<LightUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Surfaces are lit where they're no deeper than the shadow map; linear filtering blends four comparisons
+        let shadow_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        // Stand-in until a shadow map is drawn; the shader never samples it while shadows are off
+        let placeholder = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Placeholder Shadow Map"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let placeholder_view = placeholder.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &buffer, &placeholder_view, &shadow_sampler);
+
+        Self {
+            buffer,
+            shadow_sampler,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        buffer: &wgpu::Buffer,
+        shadow_map: &wgpu::TextureView,
+        shadow_sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Light Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(shadow_map),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(shadow_sampler),
+                },
+            ],
+        })
+    }
+
+    /// Bind a new shadow map, after the previous one was replaced
+    pub fn set_shadow_map(&mut self, device: &wgpu::Device, shadow_map: &wgpu::TextureView) {
+        self.bind_group =
+            Self::create_bind_group(device, &self.bind_group_layout, &self.buffer, shadow_map, &self.shadow_sampler);
+    }
+
+    /// Upload the lights for this frame; the camera position is needed for specular highlights
+    ///
+    /// `time` is the seconds since the renderer started, for shaders that animate.
+    /// Shadows are looked up with `shadow_view_proj` when it's given and the
+    /// directional light has them enabled.
+    pub fn update(
+        &self,
+        queue: &wgpu::Queue,
+        lighting: &Lighting,
+        camera_position: Vec3,
+        time: f32,
+        shadow_view_proj: Option<Mat4>,
+    ) {
+        let mut uniform = lighting.to_uniform(camera_position);
+        uniform.time = time;
+        if let (Some(view_proj), Some(settings)) = (shadow_view_proj, lighting.shadow_settings()) {
+            uniform.shadow_view_proj = view_proj.to_cols_array_2d();
+            uniform.shadow = [
+                1.0,
+                settings.depth_bias,
+                1.0 / settings.resolution.max(1) as f32,
+                settings.pcf_radius as f32,
+            ];
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    /// Get the bind group layout for pipeline creation
+    pub fn get_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_matches_shader_layout() {
+        // 5 vec4s, the point light count and time padded to 16 bytes, 2 vec4s per point light,
+        // then the shadow matrix and settings
+        assert_eq!(mem::size_of::<LightUniform>(), 5 * 16 + 16 + MAX_POINT_LIGHTS * 32 + 64 + 16);
+    }
+
+    #[test]
+    fn test_uniform_packs_lights() {
+        let mut lighting = Lighting::default();
+        for i in 0..MAX_POINT_LIGHTS + 2 {
+            lighting.add_point_light(PointLight::new(Vec3::new(i as f32, 0.0, 0.0), [1.0, 0.5, 0.0], 2.0, 5.0));
+        }
+
+        let uniform = lighting.to_uniform(Vec3::new(0.0, 0.0, 3.0));
+        assert_eq!(uniform.point_light_count, MAX_POINT_LIGHTS as u32);
+        assert_eq!(uniform.point_lights[1].position_range, [1.0, 0.0, 0.0, 5.0]);
+        assert_eq!(uniform.point_lights[1].color, [2.0, 1.0, 0.0, 0.0]);
+        assert_eq!(uniform.directional_direction[3], 1.0);
+        let direction = Vec3::from_slice(&uniform.directional_direction[..3]);
+        assert!((direction.length() - 1.0).abs() < 1e-5);
+
+        let unlit = Lighting::unlit().to_uniform(Vec3::ZERO);
+        assert_eq!(unlit.ambient, [1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(unlit.directional_direction[3], 0.0);
+        assert_eq!(unlit.point_light_count, 0);
+        assert_eq!(unlit.shadow[0], 0.0);
+
+        assert_eq!(lighting.shadow_settings(), None);
+        let shadowed = lighting.with_directional(Some(
+            DirectionalLight::new(Vec3::NEG_Y, [1.0; 3], 1.0).with_shadows(ShadowSettings::default()),
+        ));
+        assert_eq!(shadowed.shadow_settings(), Some(ShadowSettings::default()));
+    }
+}
@@ -3,31 +3,74 @@
 //! This module provides the core rendering infrastructure, separating
 //! GPU resource management from scene/entity management.
 
+pub mod background;
 pub mod camera;
 pub mod config;
+pub mod debug_draw;
 pub mod dynamic_uniforms;
+pub mod frustum;
 #[cfg(feature = "windowing")]
 pub mod gpu_context;
 #[cfg(feature = "headless")]
 pub mod headless;
+pub mod image;
+pub mod instance_buffer;
+pub mod lighting;
+pub mod picking;
 pub mod pipeline;
+pub mod pipeline_cache;
+pub mod post_process;
 pub mod render_core;
+pub mod shader;
+pub mod shadow;
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+pub mod shader_watcher;
+pub mod skybox;
+pub mod texture;
 pub mod vertex_cache;
 
+/// Device for tests that need a GPU, or `None` when no adapter is available
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) fn test_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()?;
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()
+}
+
 #[cfg(feature = "windowing")]
 use crate::renderable::{Triangle, Quad, Cube, Circle, Cylinder, Cone, Sphere};
 #[cfg(feature = "windowing")]
 use winit::window::Window;
 
 pub use camera::Camera;
-pub use config::{AntialiasingMode, CullingMode, RenderConfig};
+pub use config::{
+    AntialiasingMode, BackgroundMode, ConfigError, CullingMode, HdrConfig, PostEffect, RenderConfig, RenderMode,
+    TonemapOperator, HDR_FORMAT,
+};
 #[cfg(feature = "headless")]
 pub use headless::HeadlessRenderer;
+pub use debug_draw::{DebugDraw, DebugLineRenderer, LineVertex};
 pub use dynamic_uniforms::DynamicUniformBuffer;
+pub use frustum::Frustum;
+pub use image::{Image, ImageFileType, ImageFormat};
+pub use instance_buffer::{InstanceBuffer, InstanceData};
+pub use lighting::{DirectionalLight, LightBuffer, Lighting, PointLight, ShadowSettings, MAX_POINT_LIGHTS};
 #[cfg(feature = "windowing")]
 use super::gpu_context::{GpuContext, GpuError};
 pub use pipeline::RenderPipeline;
-pub use render_core::RenderCore;
+pub use pipeline_cache::{BlendMode, DepthState, FillMode, PipelineCache, PipelineKey};
+pub use picking::{PickError, PickResult, PickingRenderer};
+pub use post_process::PostProcessor;
+pub use render_core::{RenderCore, RenderStats};
+pub use shader::{DebugShader, ShaderError, ShaderId, BASIC_SHADER_NAME};
+pub use shadow::ShadowRenderer;
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+pub use shader_watcher::ShaderWatcher;
+pub use skybox::{EnvironmentId, SkyboxRenderer};
+pub use texture::{Texture, TextureError, TextureId, TextureStore};
 pub use vertex_cache::VertexBufferCache;
 
 /// High-level renderer that coordinates GPU resources and scene rendering
@@ -35,29 +78,48 @@ pub use vertex_cache::VertexBufferCache;
 pub struct Renderer {
     pub gpu: GpuContext,
     render_core: RenderCore,
+    /// Reloads shaders from disk when they change, if enabled
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    shader_watcher: Option<ShaderWatcher>,
 }
 
 #[cfg(feature = "windowing")]
 impl Renderer {
     pub async fn new(window: std::sync::Arc<Window>) -> Result<Self, GpuError> {
         let gpu = GpuContext::new(window).await?;
+
+        // Fall back to the best antialiasing the adapter supports instead of failing validation
+        let mut config = RenderConfig::default();
+        let requested = config.antialiasing;
+        config.antialiasing = requested.downgrade_to_supported(&gpu.adapter, &gpu.device, gpu.config.format);
+        if config.antialiasing != requested {
+            log::warn!(
+                "Antialiasing mode {:?} is not supported by this adapter, using {:?}",
+                requested,
+                config.antialiasing
+            );
+        }
+
         let render_core = RenderCore::new_windowed(
             &gpu.device,
+            &gpu.queue,
             gpu.config.format,
             gpu.config.width,
             gpu.config.height,
-            RenderConfig::default(),
+            config,
         );
 
         Ok(Self {
             gpu,
             render_core,
+            #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+            shader_watcher: None,
         })
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         self.gpu.resize(new_size);
-        self.render_core.resize(new_size.width, new_size.height);
+        self.render_core.resize(&self.gpu.device, self.gpu.config.width, self.gpu.config.height);
     }
 
     /// Get the camera for external manipulation
@@ -70,6 +132,92 @@ impl Renderer {
         self.render_core.camera_mut()
     }
 
+    /// Upload tightly packed RGBA8 pixels (sRGB, top row first) as a texture for materials
+    pub fn load_texture(&mut self, width: u32, height: u32, data: &[u8]) -> Result<TextureId, TextureError> {
+        self.render_core.textures.add_rgba8(&self.gpu.device, &self.gpu.queue, width, height, data)
+    }
+
+    /// Upload an image as a texture for materials
+    pub fn load_texture_image(&mut self, image: &Image) -> Result<TextureId, TextureError> {
+        let texture = Texture::from_image(&self.gpu.device, &self.gpu.queue, image, Some("Texture"))?;
+        Ok(self.render_core.textures.add(&self.gpu.device, texture))
+    }
+
+    /// Release a texture; objects still using it are drawn untextured
+    pub fn remove_texture(&mut self, id: TextureId) -> bool {
+        self.render_core.textures.remove(id).is_some()
+    }
+
+    /// Upload six square faces (+X, -X, +Y, -Y, +Z, -Z) as a cubemap for `BackgroundMode::Environment`
+    pub fn load_cubemap(&mut self, faces: &[Image; 6]) -> Result<EnvironmentId, TextureError> {
+        self.render_core.skybox.add_cubemap(&self.gpu.device, &self.gpu.queue, faces)
+    }
+
+    /// Upload an equirectangular panorama for `BackgroundMode::Environment`
+    pub fn load_panorama(&mut self, image: &Image) -> Result<EnvironmentId, TextureError> {
+        self.render_core.skybox.add_panorama(&self.gpu.device, &self.gpu.queue, image)
+    }
+
+    /// Release a cubemap or panorama; a background still using it shows its clear color
+    pub fn remove_environment(&mut self, id: EnvironmentId) -> bool {
+        self.render_core.skybox.remove(id)
+    }
+
+    /// Compile a WGSL shader under a name for materials to select
+    ///
+    /// Registering an existing name replaces that shader. See [`shader`] for the
+    /// inputs and bindings a shader can use. Resolves once wgpu has checked the
+    /// shader's pipelines, so errors come back here on every backend.
+    pub async fn register_shader(&mut self, name: &str, source: &str) -> Result<ShaderId, ShaderError> {
+        self.render_core.pipeline.register_shader(&self.gpu.device, name, source).await
+    }
+
+    /// Look up a registered shader by name
+    pub fn shader_id(&self, name: &str) -> Option<ShaderId> {
+        self.render_core.pipeline.cache.shaders().id(name)
+    }
+
+    /// Load every material shader in a directory and reload it whenever it changes (debug builds only)
+    ///
+    /// Each `.wgsl` file is registered as the shader named after its file stem, so
+    /// `basic.wgsl` replaces the built-in shader. The crate's other built-in shaders,
+    /// such as `sky.wgsl` and `post.wgsl`, are skipped, so `src/shaders` can be watched
+    /// directly. Shaders that fail to compile or to build their pipelines are logged,
+    /// and the previous version keeps being used.
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    pub fn watch_shaders(&mut self, directory: impl Into<std::path::PathBuf>) -> std::io::Result<()> {
+        let mut watcher = ShaderWatcher::new(directory)?;
+        watcher.reload_changed(&self.gpu.device, &mut self.render_core.pipeline);
+        self.shader_watcher = Some(watcher);
+        Ok(())
+    }
+
+    /// Stop reloading shaders from disk, keeping the versions currently loaded
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    pub fn stop_watching_shaders(&mut self) {
+        self.shader_watcher = None;
+    }
+
+    /// Get the scene's lights
+    pub fn lighting(&self) -> &Lighting {
+        self.render_core.lighting()
+    }
+
+    /// Get mutable lights for adding, removing or adjusting them
+    pub fn lighting_mut(&mut self) -> &mut Lighting {
+        self.render_core.lighting_mut()
+    }
+
+    /// Get the debug lines queued for the next frame
+    pub fn debug_draw(&self) -> &DebugDraw {
+        self.render_core.debug_draw()
+    }
+
+    /// Get mutable debug lines, for queueing lines, arrows, grids, axes and boxes drawn by the next render
+    pub fn debug_draw_mut(&mut self) -> &mut DebugDraw {
+        self.render_core.debug_draw_mut()
+    }
+
     /// Render mixed object types (all primitive types) in a single frame
     /// Objects are grouped by culling mode and rendered in separate passes to the same frame
     pub fn render_mixed_objects(
@@ -82,6 +230,12 @@ impl Renderer {
         cones: &[&Cone],
         spheres: &[&Sphere],
     ) -> Result<(), wgpu::SurfaceError> {
+        // Pick up shader edits before any pipelines are used this frame
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        if let Some(watcher) = &mut self.shader_watcher {
+            watcher.reload_changed(&self.gpu.device, &mut self.render_core.pipeline);
+        }
+
         // Create single frame output and encoder for all groups
         let output = self.gpu.surface.get_current_texture()?;
         let view = output
@@ -96,8 +250,7 @@ impl Renderer {
             });
 
         // Use the shared render core for the actual rendering
-        // Pass None for multisampled_framebuffer to avoid borrowing conflicts - 
-        // the core will handle it internally
+        // The core supplies its own multisampled targets based on the config
         self.render_core.render_mixed_objects_core(
             &self.gpu.device,
             &self.gpu.queue,
@@ -122,6 +275,20 @@ impl Renderer {
         Ok(())
     }
 
+    /// Find the entity under pixel (`x`, `y`) of the last frame, counted from the window's top-left corner
+    ///
+    /// Coordinates are physical pixels, like winit's cursor positions. Needs picking
+    /// enabled with [`Self::set_picking`] or [`RenderConfig::picking`], and resolves
+    /// to `None` when no object with an entity id covers the pixel.
+    pub async fn pick(&self, x: u32, y: u32) -> Result<Option<PickResult>, PickError> {
+        self.render_core.pick(&self.gpu.device, &self.gpu.queue, x, y).await
+    }
+
+    /// Get what the most recent render submitted, culled and drew
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_core.render_stats()
+    }
+
     /// Get renderer performance statistics
     pub fn get_cache_stats(&self) -> (usize, usize) {
         self.render_core.get_cache_stats()
@@ -133,7 +300,9 @@ impl Renderer {
     }
 
     /// Update the rendering configuration (antialiasing, culling, etc.)
-    pub fn update_config(&mut self, config: RenderConfig) {
+    /// Fails with `ConfigError::UnsupportedAntialiasing` if the adapter can't use the requested MSAA mode
+    pub fn update_config(&mut self, config: RenderConfig) -> Result<(), ConfigError> {
+        config.antialiasing.validate(&self.gpu.adapter, &self.gpu.device, config.scene_format(self.gpu.config.format))?;
         self.render_core.update_config(
             &self.gpu.device,
             self.gpu.config.format,
@@ -141,6 +310,7 @@ impl Renderer {
             self.gpu.config.width,
             self.gpu.config.height,
         );
+        Ok(())
     }
 
     /// Get the current rendering configuration
@@ -148,43 +318,87 @@ impl Renderer {
         self.render_core.get_config()
     }
 
+    /// Get the antialiasing modes the adapter supports for the format objects are drawn into
+    pub fn supported_antialiasing_modes(&self) -> Vec<AntialiasingMode> {
+        let format = self.get_config().scene_format(self.gpu.config.format);
+        AntialiasingMode::get_supported_modes(&self.gpu.adapter, &self.gpu.device, format)
+    }
+
     /// Helper method to update a single config field
-    fn update_config_field<F>(&mut self, update_fn: F)
+    fn update_config_field<F>(&mut self, update_fn: F) -> Result<(), ConfigError>
     where
         F: FnOnce(&mut RenderConfig),
     {
         let mut config = self.render_core.get_config().clone();
         update_fn(&mut config);
-        self.update_config(config);
+        self.update_config(config)
     }
 
     /// Set antialiasing mode specifically
-    pub fn set_antialiasing(&mut self, mode: AntialiasingMode) {
-        self.update_config_field(|config| config.antialiasing = mode);
+    pub fn set_antialiasing(&mut self, mode: AntialiasingMode) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.antialiasing = mode)
     }
 
     /// Set culling mode specifically
-    pub fn set_culling(&mut self, mode: CullingMode) {
-        self.update_config_field(|config| config.culling = mode);
+    pub fn set_culling(&mut self, mode: CullingMode) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.culling = mode)
     }
 
     /// Enable/disable alpha blending
-    pub fn set_alpha_blending(&mut self, enabled: bool) {
-        self.update_config_field(|config| config.alpha_blending = enabled);
+    pub fn set_alpha_blending(&mut self, enabled: bool) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.alpha_blending = enabled)
+    }
+
+    /// Set the background (solid color, transparent, or gradient)
+    pub fn set_background(&mut self, background: BackgroundMode) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.background = background)
+    }
+
+    /// Replace the post-processing chain; an empty chain renders straight to the surface
+    pub fn set_post_processing(&mut self, effects: Vec<PostEffect>) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.post_processing = effects)
+    }
+
+    /// Enable HDR rendering with the given tonemapping, or disable it with `None`
+    pub fn set_hdr(&mut self, hdr: Option<HdrConfig>) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.hdr = hdr)
+    }
+
+    /// Enable or disable the ID buffer used by [`Self::pick`]
+    pub fn set_picking(&mut self, enabled: bool) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.picking = enabled)
+    }
+
+    /// Draw objects shaded, or in one of the debug views
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) -> Result<(), ConfigError> {
+        self.update_config_field(|config| config.render_mode = render_mode)
+    }
+
+    /// Carry the current background, post-processing, HDR, picking and render mode settings over to a preset config
+    fn keep_output_settings(&self, preset: RenderConfig) -> RenderConfig {
+        let current = self.get_config();
+        RenderConfig {
+            hdr: current.hdr,
+            picking: current.picking,
+            render_mode: current.render_mode,
+            ..preset
+                .with_background(current.background)
+                .with_post_processing(current.post_processing.clone())
+        }
     }
 
-    /// Switch to 2D optimized settings (no backface culling, alpha blending)
-    pub fn set_2d_mode(&mut self) {
-        self.update_config(RenderConfig::for_2d());
+    /// Switch to 2D optimized settings (no backface culling, alpha blending), keeping the background and effects
+    pub fn set_2d_mode(&mut self) -> Result<(), ConfigError> {
+        self.update_config(self.keep_output_settings(RenderConfig::for_2d()))
     }
 
-    /// Switch to 3D optimized settings (backface culling, no alpha blending)
-    pub fn set_3d_mode(&mut self) {
-        self.update_config(RenderConfig::for_3d());
+    /// Switch to 3D optimized settings (backface culling, no alpha blending), keeping the background and effects
+    pub fn set_3d_mode(&mut self) -> Result<(), ConfigError> {
+        self.update_config(self.keep_output_settings(RenderConfig::for_3d()))
     }
 
-    /// Switch to performance mode (no antialiasing)
-    pub fn set_performance_mode(&mut self) {
-        self.update_config(RenderConfig::performance());
+    /// Switch to performance mode (no antialiasing), keeping the background and effects
+    pub fn set_performance_mode(&mut self) -> Result<(), ConfigError> {
+        self.update_config(self.keep_output_settings(RenderConfig::performance()))
     }
 }
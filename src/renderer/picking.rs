@@ -0,0 +1,361 @@
+//! Object picking
+//!
+//! With picking enabled, every visible object is drawn a second time into an
+//! R32Uint ID buffer holding its entity id, with its depth written alongside.
+//! This is a separate single-sampled pass after the frame's main pass, since
+//! integer targets can't be resolved from multisampled ones, so picking works
+//! the same with or without MSAA. Reading back one texel of each then tells
+//! which entity is under a pixel and how deep it is.
+//!
+//! Depth is written as the bits of an `f32` to a second R32Uint target rather
+//! than read from the depth buffer, since WebGL and other downlevel backends
+//! can't copy depth textures.
+//!
+//! Objects are drawn with their plain geometry, so custom shaders that move
+//! vertices are picked by their undisplaced shape.
+
+use std::collections::HashMap;
+
+use crate::renderable::Vertex;
+use crate::renderer::config::CullingMode;
+use crate::renderer::instance_buffer::InstanceData;
+use crate::renderer::pipeline_cache::DepthState;
+use crate::scene::EntityId;
+
+/// Format of the ID buffer, where 0 means no object and anything else is an entity id plus one,
+/// and of the depth target beside it
+const ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+/// Offset of the depth texel in the readback buffer, after the id texel
+const DEPTH_READBACK_OFFSET: u64 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+
+/// What was under the picked pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickResult {
+    /// Entity drawn nearest the camera at the pixel
+    pub entity: EntityId,
+    /// Its depth in the camera's 0 (near) to 1 (far) range
+    pub depth: f32,
+}
+
+/// Errors produced when a pixel can't be picked
+#[derive(Debug, Clone, PartialEq)]
+pub enum PickError {
+    /// Picking is turned off in the render config
+    Disabled,
+    /// No frame has been drawn into the ID buffer since picking was enabled or the target resized
+    NotRendered,
+    /// The GPU failed to copy or map the picked texels
+    Readback(String),
+}
+
+impl std::fmt::Display for PickError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PickError::Disabled => write!(f, "Picking needs `picking` enabled in the render config"),
+            PickError::NotRendered => write!(f, "No frame has been rendered with picking enabled yet"),
+            PickError::Readback(message) => write!(f, "Failed to read back the picked pixel: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PickError {}
+
+/// Convert an ID buffer value back to the entity it was drawn for
+pub fn entity_from_pick_id(pick_id: u32) -> Option<EntityId> {
+    pick_id.checked_sub(1)
+}
+
+/// Textures the pick pass draws into
+struct PickTargets {
+    id_texture: wgpu::Texture,
+    id_view: wgpu::TextureView,
+    /// Depth of the object in the ID buffer, as `f32` bits
+    depth_bits_texture: wgpu::Texture,
+    depth_bits_view: wgpu::TextureView,
+    /// Depth buffer testing the objects against each other
+    depth_view: wgpu::TextureView,
+}
+
+/// Draws entity ids into the ID buffer and reads them back under the cursor
+pub struct PickingRenderer {
+    shader: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    /// One pipeline per culling mode, so objects are hidden from picking exactly when they're hidden on screen
+    pipelines: HashMap<CullingMode, wgpu::RenderPipeline>,
+    /// Created on first use and dropped when the size changes or picking is turned off
+    targets: Option<PickTargets>,
+    /// Whether the targets hold a rendered frame
+    rendered: bool,
+    width: u32,
+    height: u32,
+}
+
+impl PickingRenderer {
+    /// Create the renderer, drawing with the camera bound through `camera_layout` like the main pass
+    pub fn new(device: &wgpu::Device, camera_layout: &wgpu::BindGroupLayout, width: u32, height: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Pick Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/pick.wgsl").into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pick Pipeline Layout"),
+            bind_group_layouts: &[camera_layout],
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            shader,
+            layout,
+            pipelines: HashMap::new(),
+            targets: None,
+            rendered: false,
+            width: width.max(1),
+            height: height.max(1),
+        }
+    }
+
+    /// Match the render target's new size; textures are recreated on the next frame
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.release();
+    }
+
+    /// Drop the ID buffer, for when picking is turned off
+    pub fn release(&mut self) {
+        self.targets = None;
+        self.rendered = false;
+    }
+
+    /// Create the targets and the pipelines for the given culling modes, if missing
+    pub fn prepare(&mut self, device: &wgpu::Device, culling_modes: impl IntoIterator<Item = CullingMode>) {
+        if self.targets.is_none() {
+            self.targets = Some(self.create_targets(device));
+        }
+
+        let Self { shader, layout, pipelines, .. } = self;
+        for culling_mode in culling_modes {
+            pipelines
+                .entry(culling_mode)
+                .or_insert_with(|| Self::create_pipeline(device, shader, layout, culling_mode));
+        }
+    }
+
+    /// Pipeline for objects with the given culling mode, once [`Self::prepare`] has built it
+    pub fn pipeline(&self, culling_mode: CullingMode) -> Option<&wgpu::RenderPipeline> {
+        self.pipelines.get(&culling_mode)
+    }
+
+    /// Begin a pass drawing into the ID buffer, clearing it first when `clear` is set
+    ///
+    /// Returns `None` before [`Self::prepare`] has created the targets. Objects are
+    /// drawn with their vertex buffer in slot 0, instances in slot 1 and the camera
+    /// bound to group 0.
+    pub fn begin_pass<'e>(&mut self, encoder: &'e mut wgpu::CommandEncoder, clear: bool) -> Option<wgpu::RenderPass<'e>> {
+        let targets = self.targets.as_ref()?;
+        // Frames drawn on top of an uncleared one can only be picked if that one was too
+        self.rendered = clear || self.rendered;
+
+        let color_ops = wgpu::Operations {
+            load: if clear { wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT) } else { wgpu::LoadOp::Load },
+            store: wgpu::StoreOp::Store,
+        };
+        let color_attachment = |view| {
+            Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: color_ops,
+                depth_slice: None,
+            })
+        };
+        Some(encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Pick Render Pass"),
+            color_attachments: &[color_attachment(&targets.id_view), color_attachment(&targets.depth_bits_view)],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &targets.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: if clear { wgpu::LoadOp::Clear(1.0) } else { wgpu::LoadOp::Load },
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        }))
+    }
+
+    /// Read back the entity and depth at pixel (`x`, `y`), counted from the top-left corner
+    ///
+    /// Resolves to `None` when the pixel is outside the frame or no object with an
+    /// entity id covers it. Natively this blocks on the device until the copy is
+    /// done; on the web it waits for the browser to map the buffer.
+    pub async fn pick(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        x: u32,
+        y: u32,
+    ) -> Result<Option<PickResult>, PickError> {
+        let targets = match &self.targets {
+            Some(targets) if self.rendered => targets,
+            _ => return Err(PickError::NotRendered),
+        };
+        if x >= self.width || y >= self.height {
+            return Ok(None);
+        }
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Readback Buffer"),
+            size: DEPTH_READBACK_OFFSET + 4,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pick Readback Encoder"),
+        });
+        for (texture, offset) in [(&targets.id_texture, 0), (&targets.depth_bits_texture, DEPTH_READBACK_OFFSET)] {
+            encoder.copy_texture_to_buffer(
+                wgpu::TexelCopyTextureInfo {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::TexelCopyBufferInfo {
+                    buffer: &readback_buffer,
+                    layout: wgpu::TexelCopyBufferLayout {
+                        offset,
+                        bytes_per_row: None,
+                        rows_per_image: None,
+                    },
+                },
+                wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let buffer_slice = readback_buffer.slice(..);
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        device
+            .poll(wgpu::PollType::Wait)
+            .map_err(|error| PickError::Readback(error.to_string()))?;
+
+        match receiver.await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => return Err(PickError::Readback(error.to_string())),
+            Err(_) => return Err(PickError::Readback("the buffer mapping was cancelled".to_string())),
+        }
+
+        let (pick_id, depth) = {
+            let data = buffer_slice.get_mapped_range();
+            let texel = |offset: usize| [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+            (
+                u32::from_le_bytes(texel(0)),
+                f32::from_le_bytes(texel(DEPTH_READBACK_OFFSET as usize)),
+            )
+        };
+        readback_buffer.unmap();
+
+        Ok(entity_from_pick_id(pick_id).map(|entity| PickResult { entity, depth }))
+    }
+
+    fn create_targets(&self, device: &wgpu::Device) -> PickTargets {
+        log::debug!("Creating {}x{} pick targets", self.width, self.height);
+        let create_texture = |label: &str, format: wgpu::TextureFormat, usage: wgpu::TextureUsages| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: self.width,
+                    height: self.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | usage,
+                view_formats: &[],
+            })
+        };
+
+        // COPY_SRC so single texels can be read back
+        let id_texture = create_texture("Pick ID Texture", ID_FORMAT, wgpu::TextureUsages::COPY_SRC);
+        let depth_bits_texture = create_texture("Pick Depth Bits Texture", ID_FORMAT, wgpu::TextureUsages::COPY_SRC);
+        let depth_texture = create_texture("Pick Depth Texture", DepthState::default().format, wgpu::TextureUsages::empty());
+        PickTargets {
+            id_view: id_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            depth_bits_view: depth_bits_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            depth_view: depth_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            id_texture,
+            depth_bits_texture,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        culling_mode: CullingMode,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pick Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[Vertex::desc(), InstanceData::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                // Integer targets can't be blended
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: ID_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: ID_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: culling_mode.to_wgpu(),
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthState::default().to_wgpu()),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_ids_round_trip() {
+        let data = InstanceData::from_matrix(glam::Mat4::IDENTITY);
+        assert_eq!(entity_from_pick_id(data.pick_id), None);
+        for entity in [0, 1, 41] {
+            assert_eq!(entity_from_pick_id(data.with_entity_id(Some(entity)).pick_id), Some(entity));
+        }
+    }
+}
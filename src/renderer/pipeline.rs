@@ -2,59 +2,58 @@
 //!
 //! Handles the graphics pipeline setup and rendering operations
 
-use crate::renderable::Vertex;
 use crate::renderer::config::RenderConfig;
+use crate::renderer::pipeline_cache::{PipelineCache, PipelineKey};
+use crate::renderer::shader::{ShaderError, ShaderId};
 
 #[cfg(feature = "windowing")]
 use crate::renderer::GpuContext;
 
 /// Manages the rendering pipeline and draw operations
 pub struct RenderPipeline {
-    pub pipeline: wgpu::RenderPipeline,
+    /// Pipelines for every render state used so far
+    pub cache: PipelineCache,
     pub config: RenderConfig,
+    /// Format of the surface or output texture; see `RenderConfig::scene_format` for the one drawn into
     pub format: wgpu::TextureFormat,
-    #[allow(dead_code)]
+    /// Multisampled color target that resolves into the surface/output texture
     pub multisampled_framebuffer: Option<wgpu::TextureView>,
+    /// Multisampled depth target used alongside `multisampled_framebuffer`
+    pub multisampled_depth: Option<wgpu::TextureView>,
 }
 
 impl RenderPipeline {
     #[cfg(feature = "windowing")]
-    pub fn new(gpu: &GpuContext, uniform_bind_group_layout: &wgpu::BindGroupLayout) -> Self {
-        Self::new_with_config(gpu, uniform_bind_group_layout, RenderConfig::default())
+    pub fn new(gpu: &GpuContext, bind_group_layouts: &[&wgpu::BindGroupLayout]) -> Self {
+        Self::new_with_config(gpu, bind_group_layouts, RenderConfig::default())
     }
 
     #[cfg(feature = "windowing")]
     pub fn new_with_config(
         gpu: &GpuContext,
-        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
         config: RenderConfig,
     ) -> Self {
-        let (pipeline, multisampled_framebuffer) = Self::create_pipeline_with_config(
+        Self::new_with_config_core(
             &gpu.device,
-            uniform_bind_group_layout,
+            bind_group_layouts,
             gpu.config.format,
-            &config,
+            config,
             gpu.config.width,
             gpu.config.height,
-        );
-        Self {
-            pipeline,
-            config,
-            format: gpu.config.format,
-            multisampled_framebuffer,
-        }
+        )
     }
 
     #[cfg(feature = "headless")]
     pub fn new_headless(
         device: &wgpu::Device,
-        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
         width: u32,
         height: u32,
     ) -> anyhow::Result<Self> {
         Self::new_headless_with_config(
             device,
-            uniform_bind_group_layout,
+            bind_group_layouts,
             width,
             height,
             RenderConfig::default(),
@@ -64,197 +63,130 @@ impl RenderPipeline {
     #[cfg(feature = "headless")]
     pub fn new_headless_with_config(
         device: &wgpu::Device,
-        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
         width: u32,
         height: u32,
         config: RenderConfig,
     ) -> anyhow::Result<Self> {
-        let (pipeline, multisampled_framebuffer) = Self::create_pipeline_with_config(
+        Ok(Self::new_with_config_core(
             device,
-            uniform_bind_group_layout,
+            bind_group_layouts,
             wgpu::TextureFormat::Rgba8UnormSrgb,
-            &config,
+            config,
             width,
             height,
-        );
-        Ok(Self {
-            pipeline,
-            config,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            multisampled_framebuffer,
-        })
+        ))
     }
 
-    fn create_pipeline_with_config(
+    /// Create the multisampled color and depth targets for the configured sample count
+    /// Returns (None, None) when antialiasing is disabled
+    fn create_multisampled_targets(
         device: &wgpu::Device,
-        uniform_bind_group_layout: &wgpu::BindGroupLayout,
         format: wgpu::TextureFormat,
         config: &RenderConfig,
         width: u32,
         height: u32,
-    ) -> (wgpu::RenderPipeline, Option<wgpu::TextureView>) {
-        // Load shader
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Basic Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/basic.wgsl").into()),
-        });
-
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[uniform_bind_group_layout],
-                push_constant_ranges: &[],
-            });
+    ) -> (Option<wgpu::TextureView>, Option<wgpu::TextureView>) {
+        if !config.antialiasing.is_multisampled() {
+            return (None, None);
+        }
 
-        // Create multisampled framebuffer if needed
-        let multisampled_framebuffer = if config.antialiasing.is_multisampled() {
-            let multisampled_texture_desc = wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width,
-                    height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: config.antialiasing.sample_count(),
-                dimension: wgpu::TextureDimension::D2,
-                format,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                label: Some("Multisampled framebuffer"),
-                view_formats: &[],
-            };
-            let multisampled_texture = device.create_texture(&multisampled_texture_desc);
-            Some(multisampled_texture.create_view(&wgpu::TextureViewDescriptor::default()))
-        } else {
-            None
+        let size = wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
         };
 
-        // Configure blend state based on alpha blending setting
-        let blend_state = if config.alpha_blending {
-            Some(wgpu::BlendState::ALPHA_BLENDING)
-        } else {
-            Some(wgpu::BlendState::REPLACE)
-        };
+        let color_texture = device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: config.antialiasing.sample_count(),
+            dimension: wgpu::TextureDimension::D2,
+            format: config.scene_format(format),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Multisampled framebuffer"),
+            view_formats: &[],
+        });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: blend_state,
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: config.culling.to_wgpu(),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: config.antialiasing.sample_count(),
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
+        // Depth attachments must match the color attachment's sample count
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: config.antialiasing.sample_count(),
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Multisampled depth buffer"),
+            view_formats: &[],
         });
 
-        (pipeline, multisampled_framebuffer)
+        (
+            Some(color_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            Some(depth_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+        )
+    }
+
+    /// Recreate the multisampled targets for a new render target size
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let (multisampled_framebuffer, multisampled_depth) =
+            Self::create_multisampled_targets(device, self.format, &self.config, width, height);
+        self.multisampled_framebuffer = multisampled_framebuffer;
+        self.multisampled_depth = multisampled_depth;
     }
 
-    /// Update the render configuration and recreate the pipeline
+    /// Update the render configuration, building its pipeline if it isn't cached yet
     pub fn update_config(
         &mut self,
         device: &wgpu::Device,
-        uniform_bind_group_layout: &wgpu::BindGroupLayout,
         format: wgpu::TextureFormat,
         config: RenderConfig,
         width: u32,
         height: u32,
     ) {
-        let (pipeline, multisampled_framebuffer) = Self::create_pipeline_with_config(
-            device,
-            uniform_bind_group_layout,
-            format,
-            &config,
-            width,
-            height,
-        );
-        self.pipeline = pipeline;
+        self.cache.get_or_create(device, PipelineKey::from_config(&config, format));
+        let (multisampled_framebuffer, multisampled_depth) =
+            Self::create_multisampled_targets(device, format, &config, width, height);
         self.config = config;
         self.format = format;
         self.multisampled_framebuffer = multisampled_framebuffer;
-    }
-
-    /// Create a temporary pipeline with a specific culling mode
-    /// Used for per-object culling support
-    pub fn create_culling_pipeline(
-        device: &wgpu::Device,
-        uniform_bind_group_layout: &wgpu::BindGroupLayout,
-        format: wgpu::TextureFormat,
-        base_config: &RenderConfig,
-        culling_mode: crate::renderer::config::CullingMode,
-    ) -> wgpu::RenderPipeline {
-        let mut config = base_config.clone();
-        config.culling = culling_mode;
-        let (pipeline, _) = Self::create_pipeline_with_config(
-            device,
-            uniform_bind_group_layout,
-            format,
-            &config,
-            1, // Width and height don't matter for pipeline creation when not using multisampling
-            1,
-        );
-        pipeline
+        self.multisampled_depth = multisampled_depth;
     }
 
     /// Create pipeline with core method name for RenderCore compatibility
     pub fn new_with_config_core(
         device: &wgpu::Device,
-        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
         format: wgpu::TextureFormat,
         config: RenderConfig,
         width: u32,
         height: u32,
     ) -> Self {
-        let (pipeline, multisampled_framebuffer) = Self::create_pipeline_with_config(
-            device,
-            uniform_bind_group_layout,
-            format,
-            &config,
-            width,
-            height,
-        );
+        let mut cache = PipelineCache::new(device, bind_group_layouts);
+        cache.get_or_create(device, PipelineKey::from_config(&config, format));
+        let (multisampled_framebuffer, multisampled_depth) =
+            Self::create_multisampled_targets(device, format, &config, width, height);
         Self {
-            pipeline,
+            cache,
             config,
             format,
             multisampled_framebuffer,
+            multisampled_depth,
         }
     }
 
-    /// Get the current texture format used by this pipeline
+    /// Get the format of the surface or output texture this pipeline renders for
     pub fn get_format(&self) -> wgpu::TextureFormat {
         self.format
     }
+
+    /// Key of the pipeline described by the current config and format
+    pub fn base_key(&self) -> PipelineKey {
+        PipelineKey::from_config(&self.config, self.format)
+    }
+
+    /// Register a shader with the cache, trying a new one out with the current render state
+    pub async fn register_shader(&mut self, device: &wgpu::Device, name: &str, source: &str) -> Result<ShaderId, ShaderError> {
+        let trial_key = self.base_key();
+        self.cache.register_shader(device, name, source, trial_key).await
+    }
 }
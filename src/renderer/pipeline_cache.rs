@@ -0,0 +1,412 @@
+//! Persistent cache of render pipelines keyed by render state
+//!
+//! Building a `wgpu::RenderPipeline` is expensive, so every distinct combination
+//! of render state and shader gets its pipeline built once and reused on later frames.
+
+use std::collections::HashMap;
+
+use crate::renderable::Vertex;
+use crate::renderer::config::{CullingMode, RenderConfig};
+use crate::renderer::instance_buffer::InstanceData;
+use crate::renderer::shader::{
+    create_module, validate_wgsl, ShaderError, ShaderId, ShaderRegistry, FRAGMENT_ENTRY_POINT, VERTEX_ENTRY_POINT,
+};
+
+/// How fragments are combined with the render target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Overwrite the target color
+    Replace,
+    /// Standard alpha blending
+    Alpha,
+}
+
+impl BlendMode {
+    /// Blend mode matching the config's alpha blending flag
+    pub fn from_alpha_blending(alpha_blending: bool) -> Self {
+        if alpha_blending {
+            BlendMode::Alpha
+        } else {
+            BlendMode::Replace
+        }
+    }
+
+    /// Convert to wgpu blend state
+    pub fn to_wgpu(&self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Replace => wgpu::BlendState::REPLACE,
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+        }
+    }
+}
+
+/// Depth test and write settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DepthState {
+    pub format: wgpu::TextureFormat,
+    pub write_enabled: bool,
+    pub compare: wgpu::CompareFunction,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            format: wgpu::TextureFormat::Depth32Float,
+            write_enabled: true,
+            compare: wgpu::CompareFunction::Less,
+        }
+    }
+}
+
+impl DepthState {
+    /// Convert to wgpu depth stencil state
+    pub fn to_wgpu(&self) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: self.format,
+            depth_write_enabled: self.write_enabled,
+            depth_compare: self.compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+/// Which parts of each triangle are rasterized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FillMode {
+    /// Whole triangles
+    #[default]
+    Solid,
+    /// Triangle edges only, which needs the device's `POLYGON_MODE_LINE` feature
+    Lines,
+    /// Vertices only, drawn as single pixels
+    Points,
+}
+
+impl FillMode {
+    /// Primitive topology the vertices are assembled into
+    pub fn topology(&self) -> wgpu::PrimitiveTopology {
+        match self {
+            FillMode::Solid | FillMode::Lines => wgpu::PrimitiveTopology::TriangleList,
+            FillMode::Points => wgpu::PrimitiveTopology::PointList,
+        }
+    }
+
+    /// How assembled triangles are rasterized
+    pub fn polygon_mode(&self) -> wgpu::PolygonMode {
+        match self {
+            FillMode::Lines => wgpu::PolygonMode::Line,
+            FillMode::Solid | FillMode::Points => wgpu::PolygonMode::Fill,
+        }
+    }
+}
+
+/// Everything that distinguishes one render pipeline from another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub culling: CullingMode,
+    pub blend: BlendMode,
+    pub sample_count: u32,
+    pub format: wgpu::TextureFormat,
+    pub depth: DepthState,
+    pub shader: ShaderId,
+    pub fill: FillMode,
+}
+
+impl PipelineKey {
+    /// Key for the pipeline described by a render config and output format
+    /// HDR configs draw into the HDR format rather than the output's
+    pub fn from_config(config: &RenderConfig, format: wgpu::TextureFormat) -> Self {
+        Self {
+            culling: config.culling,
+            blend: BlendMode::from_alpha_blending(config.alpha_blending),
+            sample_count: config.antialiasing.sample_count(),
+            format: config.scene_format(format),
+            depth: DepthState::default(),
+            shader: ShaderId::BASIC,
+            fill: FillMode::Solid,
+        }
+    }
+
+    /// Same key with a different culling mode, for per-object culling
+    pub fn with_culling(self, culling: CullingMode) -> Self {
+        Self { culling, ..self }
+    }
+
+    /// Same key with a different shader, for per-object shaders
+    pub fn with_shader(self, shader: ShaderId) -> Self {
+        Self { shader, ..self }
+    }
+
+    /// Same key rasterizing only edges or vertices, for debug views
+    pub fn with_fill(self, fill: FillMode) -> Self {
+        Self { fill, ..self }
+    }
+
+    /// Same key for translucent objects, which blend over what's behind them
+    /// and test against the depth buffer without writing to it
+    pub fn transparent(self) -> Self {
+        Self {
+            blend: BlendMode::Alpha,
+            depth: DepthState {
+                write_enabled: false,
+                ..self.depth
+            },
+            ..self
+        }
+    }
+}
+
+/// Render pipelines built so far, sharing the registered shaders and one pipeline layout
+pub struct PipelineCache {
+    shaders: ShaderRegistry,
+    layout: wgpu::PipelineLayout,
+    pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+}
+
+impl PipelineCache {
+    /// Create an empty cache whose pipelines use `bind_group_layouts` in group order
+    pub fn new(device: &wgpu::Device, bind_group_layouts: &[&wgpu::BindGroupLayout]) -> Self {
+        let shaders = ShaderRegistry::new(device);
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            shaders,
+            layout,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Get the pipeline for a key, building it on first use
+    pub fn get_or_create(&mut self, device: &wgpu::Device, key: PipelineKey) -> &wgpu::RenderPipeline {
+        let Self { shaders, layout, pipelines } = self;
+        pipelines.entry(key).or_insert_with(|| {
+            log::debug!("Building render pipeline for {:?}", key);
+            Self::create_pipeline(device, shaders.module(key.shader), layout, &key)
+        })
+    }
+
+    /// Compile a shader under a name, replacing any shader already registered with it
+    ///
+    /// Every pipeline built from a replaced shader is rebuilt straight away, and a new
+    /// shader gets a trial pipeline for `trial_key`, so wgpu's errors are reported here as
+    /// [`ShaderError::Pipeline`] rather than when the shader is next drawn. On error
+    /// the previous shader and its pipelines stay in use. Resolves once wgpu has
+    /// reported back, which on WebGPU is after the device has checked the pipelines.
+    pub async fn register_shader(
+        &mut self,
+        device: &wgpu::Device,
+        name: &str,
+        source: &str,
+        trial_key: PipelineKey,
+    ) -> Result<ShaderId, ShaderError> {
+        validate_wgsl(name, source)?;
+        let keys: Vec<PipelineKey> = match self.shaders.id(name) {
+            Some(id) => self.pipelines.keys().filter(|key| key.shader == id).copied().collect(),
+            None => Vec::new(),
+        };
+
+        // Nothing in the cache changes until wgpu has accepted the module and its pipelines
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = create_module(device, name, source);
+        let pipelines: Vec<(PipelineKey, wgpu::RenderPipeline)> = keys
+            .iter()
+            .map(|key| (*key, Self::create_pipeline(device, &module, &self.layout, key)))
+            .collect();
+        if keys.is_empty() {
+            Self::create_pipeline(device, &module, &self.layout, &trial_key);
+        }
+        if let Some(error) = device.pop_error_scope().await {
+            return Err(ShaderError::Pipeline {
+                name: name.to_string(),
+                message: error.to_string(),
+            });
+        }
+
+        let id = self.shaders.insert(name, module);
+        self.pipelines.extend(pipelines);
+        Ok(id)
+    }
+
+    /// Registered shaders
+    pub fn shaders(&self) -> &ShaderRegistry {
+        &self.shaders
+    }
+
+    /// Get a previously built pipeline
+    pub fn get(&self, key: &PipelineKey) -> Option<&wgpu::RenderPipeline> {
+        self.pipelines.get(key)
+    }
+
+    /// Number of pipelines built so far
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    /// Drop every cached pipeline
+    pub fn clear(&mut self) {
+        self.pipelines.clear();
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        key: &PipelineKey,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some(VERTEX_ENTRY_POINT),
+                buffers: &[Vertex::desc(), InstanceData::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(FRAGMENT_ENTRY_POINT),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: key.format,
+                    blend: Some(key.blend.to_wgpu()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: key.fill.topology(),
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: key.culling.to_wgpu(),
+                polygon_mode: key.fill.polygon_mode(),
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(key.depth.to_wgpu()),
+            multisample: wgpu::MultisampleState {
+                count: key.sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::config::AntialiasingMode;
+
+    #[test]
+    fn test_key_from_config() {
+        let key = PipelineKey::from_config(&RenderConfig::for_2d(), wgpu::TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(key.culling, CullingMode::None);
+        assert_eq!(key.blend, BlendMode::Alpha);
+        assert_eq!(key.sample_count, AntialiasingMode::Msaa4x.sample_count());
+        assert_eq!(key.depth, DepthState::default());
+    }
+
+    #[test]
+    fn test_key_distinguishes_render_state() {
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let base = PipelineKey::from_config(&RenderConfig::default(), format);
+
+        assert_eq!(base, PipelineKey::from_config(&RenderConfig::default(), format));
+        assert_eq!(base.with_culling(base.culling), base);
+        assert_ne!(base.with_culling(CullingMode::None), base);
+        assert_eq!(base.shader, ShaderId::BASIC);
+        assert_ne!(PipelineKey::from_config(&RenderConfig::performance(), format), base);
+        assert_ne!(PipelineKey::from_config(&RenderConfig::default(), wgpu::TextureFormat::Bgra8UnormSrgb), base);
+    }
+
+    #[test]
+    fn test_transparent_key_blends_without_writing_depth() {
+        let base = PipelineKey::from_config(&RenderConfig::for_3d(), wgpu::TextureFormat::Rgba8UnormSrgb);
+        let transparent = base.transparent();
+        assert_eq!(base.blend, BlendMode::Replace);
+        assert_eq!(transparent.blend, BlendMode::Alpha);
+        assert!(!transparent.depth.write_enabled);
+        assert_eq!(transparent.depth.compare, base.depth.compare);
+        assert_eq!(transparent.with_culling(base.culling).with_shader(base.shader), transparent);
+    }
+
+    #[test]
+    fn test_fill_mode_selects_rasterization() {
+        let base = PipelineKey::from_config(&RenderConfig::default(), wgpu::TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(base.fill, FillMode::Solid);
+        assert_ne!(base.with_fill(FillMode::Lines), base);
+        assert_eq!(base.with_fill(FillMode::Lines).with_fill(FillMode::Solid), base);
+
+        assert_eq!(FillMode::Lines.topology(), wgpu::PrimitiveTopology::TriangleList);
+        assert_eq!(FillMode::Lines.polygon_mode(), wgpu::PolygonMode::Line);
+        assert_eq!(FillMode::Points.topology(), wgpu::PrimitiveTopology::PointList);
+        assert_eq!(FillMode::Points.polygon_mode(), wgpu::PolygonMode::Fill);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_rejected_shaders_keep_the_previous_version() {
+        use crate::renderer::{dynamic_uniforms::DynamicUniformBuffer, lighting::LightBuffer, texture::TextureStore};
+
+        let (device, queue) = crate::renderer::test_device().expect("no GPU adapter available");
+        let uniforms = DynamicUniformBuffer::new(&device);
+        let lights = LightBuffer::new(&device);
+        let textures = TextureStore::new(&device, &queue);
+        let mut cache = PipelineCache::new(
+            &device,
+            &[uniforms.get_bind_group_layout(), lights.get_bind_group_layout(), textures.get_bind_group_layout()],
+        );
+
+        let trial_key = PipelineKey::from_config(&RenderConfig::default(), wgpu::TextureFormat::Rgba8UnormSrgb);
+        let register = |cache: &mut PipelineCache, name: &str, source: &str| {
+            pollster::block_on(cache.register_shader(&device, name, source, trial_key))
+        };
+
+        let basic = include_str!("../shaders/basic.wgsl");
+        let id = register(&mut cache, "custom", basic).unwrap();
+        let key = trial_key.with_shader(id);
+        cache.get_or_create(&device, key);
+
+        // Valid WGSL, but reading a vertex input no buffer provides
+        let mismatched_input = basic.replace("@location(3) uv: vec2<f32>", "@location(4) uv: vec2<f32>");
+        assert!(matches!(
+            register(&mut cache, "custom", &mismatched_input),
+            Err(ShaderError::Interface { .. })
+        ));
+
+        // Follows the contract, but the fragment stage reads a value the vertex stage never writes
+        let mismatched_stages = "
+            @group(0) @binding(0) var<uniform> view_proj: mat4x4<f32>;
+            @vertex fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+                return view_proj * vec4<f32>(position, 1.0);
+            }
+            @fragment fn fs_main(@location(7) tint: vec4<f32>) -> @location(0) vec4<f32> { return tint; }";
+        assert!(matches!(
+            register(&mut cache, "custom", mismatched_stages),
+            Err(ShaderError::Pipeline { .. })
+        ));
+        assert!(matches!(
+            register(&mut cache, "fresh", mismatched_stages),
+            Err(ShaderError::Pipeline { .. })
+        ));
+
+        assert_eq!(cache.shaders().id("custom"), Some(id));
+        assert_eq!(cache.shaders().id("fresh"), None);
+        assert!(cache.get(&key).is_some());
+
+        // A working replacement has its pipelines rebuilt straight away
+        assert_eq!(register(&mut cache, "custom", basic), Ok(id));
+        assert!(cache.get(&key).is_some());
+    }
+}
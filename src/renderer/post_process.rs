@@ -0,0 +1,463 @@
+//! Post-processing effect chain
+//!
+//! When the config lists any [`PostEffect`]s, objects are drawn into an
+//! offscreen scene texture instead of the surface or output texture. Each
+//! effect then runs as one or more fullscreen passes, alternating between two
+//! intermediate textures, and the last pass writes the real render target.
+//! The scene texture itself is never overwritten by the passes.
+//!
+//! With HDR enabled the scene and intermediate textures use the HDR format,
+//! and a tonemap pass is appended to the chain to convert the frame into the
+//! output's range and format.
+
+use std::collections::HashMap;
+
+use crate::renderer::config::{PostEffect, RenderConfig, TonemapOperator};
+use crate::renderer::dynamic_uniforms::grown_capacity;
+
+/// Fragment shader run by a single fullscreen pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PassKind {
+    Fxaa,
+    Tonemap,
+    Vignette,
+    Grayscale,
+    Blur,
+}
+
+impl PassKind {
+    fn entry_point(self) -> &'static str {
+        match self {
+            PassKind::Fxaa => "fs_fxaa",
+            PassKind::Tonemap => "fs_tonemap",
+            PassKind::Vignette => "fs_vignette",
+            PassKind::Grayscale => "fs_grayscale",
+            PassKind::Blur => "fs_blur",
+        }
+    }
+}
+
+/// A fullscreen pass and the parameters its shader reads from `settings.params`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PostPass {
+    kind: PassKind,
+    params: [f32; 4],
+}
+
+/// Expand effects into the passes that implement them; a gaussian blur takes two
+fn passes_for(effects: &[PostEffect]) -> Vec<PostPass> {
+    let mut passes = Vec::with_capacity(effects.len());
+    for effect in effects {
+        match *effect {
+            PostEffect::Fxaa => passes.push(PostPass {
+                kind: PassKind::Fxaa,
+                params: [0.0; 4],
+            }),
+            PostEffect::Tonemap { operator, exposure } => {
+                let operator = match operator {
+                    TonemapOperator::Reinhard => 0.0,
+                    TonemapOperator::Aces => 1.0,
+                };
+                passes.push(PostPass {
+                    kind: PassKind::Tonemap,
+                    params: [exposure, operator, 0.0, 0.0],
+                });
+            }
+            PostEffect::Vignette { strength, radius } => passes.push(PostPass {
+                kind: PassKind::Vignette,
+                params: [strength.clamp(0.0, 1.0), radius, 0.0, 0.0],
+            }),
+            PostEffect::Grayscale { amount } => passes.push(PostPass {
+                kind: PassKind::Grayscale,
+                params: [amount.clamp(0.0, 1.0), 0.0, 0.0, 0.0],
+            }),
+            PostEffect::GaussianBlur { sigma } => {
+                // Horizontal then vertical, equivalent to a 2D gaussian
+                for direction in [[1.0, 0.0], [0.0, 1.0]] {
+                    passes.push(PostPass {
+                        kind: PassKind::Blur,
+                        params: [sigma, direction[0], direction[1], 0.0],
+                    });
+                }
+            }
+        }
+    }
+    passes
+}
+
+/// A pass's settings as laid out in the shader's uniform block
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PassUniform {
+    params: [f32; 4],
+    texel_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+/// Scene texture followed by the intermediates the passes alternate between
+struct Targets {
+    format: wgpu::TextureFormat,
+    scene_texture: wgpu::Texture,
+    views: Vec<wgpu::TextureView>,
+    /// One bind group per view, for passes that read from it
+    bind_groups: Vec<wgpu::BindGroup>,
+}
+
+/// Runs the configured post-processing chain after the frame's render pass
+pub struct PostProcessor {
+    shader: wgpu::ShaderModule,
+    bind_group_layout: wgpu::BindGroupLayout,
+    layout: wgpu::PipelineLayout,
+    sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    /// Bytes between passes' settings, padded to the dynamic offset alignment
+    uniform_stride: u64,
+    uniform_capacity: usize,
+    pipelines: HashMap<(PassKind, wgpu::TextureFormat), wgpu::RenderPipeline>,
+    /// Created on first use and dropped when the size changes
+    targets: Option<Targets>,
+    passes: Vec<PostPass>,
+    /// Format the last pass writes, which may differ from the targets' format
+    output_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+}
+
+impl PostProcessor {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post-Processing Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/post.wgsl").into()),
+        });
+
+        let uniform_size = std::mem::size_of::<PassUniform>() as u64;
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post-Processing Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(uniform_size),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post-Processing Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Clamp so blurs and FXAA don't pull in pixels from the opposite edge
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post-Processing Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let uniform_stride = uniform_size.div_ceil(alignment) * alignment;
+        let uniform_capacity = 4;
+
+        Self {
+            shader,
+            bind_group_layout,
+            layout,
+            sampler,
+            uniform_buffer: Self::create_uniform_buffer(device, uniform_stride, uniform_capacity),
+            uniform_stride,
+            uniform_capacity,
+            pipelines: HashMap::new(),
+            targets: None,
+            passes: Vec::new(),
+            output_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: width.max(1),
+            height: height.max(1),
+        }
+    }
+
+    fn create_uniform_buffer(device: &wgpu::Device, stride: u64, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post-Processing Uniform Buffer"),
+            size: stride * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Match the render target's new size; textures are recreated on the next frame
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.targets = None;
+    }
+
+    /// Set up the config's effect chain for a frame written to a target of `output_format`
+    ///
+    /// Returns false when there are no effects and no HDR tonemapping, in which case
+    /// the frame should be rendered straight into the target and [`Self::run`] not called.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &RenderConfig,
+        output_format: wgpu::TextureFormat,
+    ) -> bool {
+        self.passes = passes_for(&config.post_processing);
+        if let Some(hdr) = &config.hdr {
+            self.passes.extend(passes_for(&[hdr.tonemap_effect()]));
+        }
+        if self.passes.is_empty() {
+            return false;
+        }
+        let format = config.scene_format(output_format);
+        self.output_format = output_format;
+
+        let mut rebuild_bind_groups = false;
+        if self.passes.len() > self.uniform_capacity {
+            self.uniform_capacity = grown_capacity(self.uniform_capacity, self.passes.len());
+            self.uniform_buffer = Self::create_uniform_buffer(device, self.uniform_stride, self.uniform_capacity);
+            rebuild_bind_groups = true;
+        }
+
+        // The scene texture, plus up to two intermediates for chains of several passes
+        let view_count = 1 + (self.passes.len() - 1).min(2);
+        let targets_fit = self
+            .targets
+            .as_ref()
+            .is_some_and(|targets| targets.format == format && targets.views.len() >= view_count);
+        if !targets_fit || rebuild_bind_groups {
+            self.targets = Some(self.create_targets(device, format, view_count));
+        }
+
+        let texel_size = [1.0 / self.width as f32, 1.0 / self.height as f32];
+        let mut data = vec![0u8; self.uniform_stride as usize * self.passes.len()];
+        for (chunk, pass) in data.chunks_exact_mut(self.uniform_stride as usize).zip(&self.passes) {
+            let uniform = PassUniform {
+                params: pass.params,
+                texel_size,
+                _padding: [0.0; 2],
+            };
+            chunk[..std::mem::size_of::<PassUniform>()].copy_from_slice(bytemuck::bytes_of(&uniform));
+        }
+        queue.write_buffer(&self.uniform_buffer, 0, &data);
+
+        let Self { shader, layout, pipelines, passes, .. } = self;
+        for (index, pass) in passes.iter().enumerate() {
+            let target_format = if index + 1 == passes.len() { output_format } else { format };
+            pipelines
+                .entry((pass.kind, target_format))
+                .or_insert_with(|| Self::create_pipeline(device, shader, layout, pass.kind, target_format));
+        }
+        true
+    }
+
+    fn create_targets(&self, device: &wgpu::Device, format: wgpu::TextureFormat, count: usize) -> Targets {
+        let textures: Vec<wgpu::Texture> = (0..count)
+            .map(|index| {
+                device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(if index == 0 {
+                        "Post-Processing Scene Texture"
+                    } else {
+                        "Post-Processing Intermediate Texture"
+                    }),
+                    size: wgpu::Extent3d {
+                        width: self.width,
+                        height: self.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    // COPY_SRC so headless renders can read back the scene before any effects
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::COPY_SRC,
+                    view_formats: &[],
+                })
+            })
+            .collect();
+        let views: Vec<wgpu::TextureView> = textures
+            .iter()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            .collect();
+
+        let bind_groups = views
+            .iter()
+            .map(|view| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Post-Processing Bind Group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                                buffer: &self.uniform_buffer,
+                                offset: 0,
+                                size: wgpu::BufferSize::new(std::mem::size_of::<PassUniform>() as u64),
+                            }),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        Targets {
+            format,
+            scene_texture: textures.into_iter().next().expect("there is always a scene texture"),
+            views,
+            bind_groups,
+        }
+    }
+
+    /// Texture the frame's objects are drawn into before the chain runs
+    ///
+    /// Only valid after [`Self::prepare`] returned true.
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.targets.as_ref().expect("post-processing targets are created by prepare").views[0]
+    }
+
+    /// The scene texture as drawn, before any effects or tonemapping, if the chain has run
+    pub fn scene_texture(&self) -> Option<&wgpu::Texture> {
+        self.targets.as_ref().map(|targets| &targets.scene_texture)
+    }
+
+    /// Record every pass, the last one writing into `target_view`
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, target_view: &wgpu::TextureView) {
+        let Some(targets) = &self.targets else {
+            return;
+        };
+
+        for (index, pass) in self.passes.iter().enumerate() {
+            // Pass 0 reads the scene; later passes alternate between the intermediates
+            let source = if index == 0 { 0 } else { 1 + (index - 1) % 2 };
+            let (destination, format) = if index + 1 == self.passes.len() {
+                (target_view, self.output_format)
+            } else {
+                (&targets.views[1 + index % 2], targets.format)
+            };
+            let Some(pipeline) = self.pipelines.get(&(pass.kind, format)) else {
+                continue;
+            };
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post-Processing Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: destination,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        // Every pixel is overwritten by the fullscreen triangle
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(
+                0,
+                &targets.bind_groups[source],
+                &[(index as u64 * self.uniform_stride) as u32],
+            );
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        kind: PassKind,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post-Processing Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(kind.entry_point()),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effects_expand_to_passes_in_order() {
+        let passes = passes_for(&[
+            PostEffect::GaussianBlur { sigma: 2.0 },
+            PostEffect::Tonemap {
+                operator: TonemapOperator::Aces,
+                exposure: 1.5,
+            },
+            PostEffect::Grayscale { amount: 3.0 },
+        ]);
+
+        let kinds: Vec<PassKind> = passes.iter().map(|pass| pass.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![PassKind::Blur, PassKind::Blur, PassKind::Tonemap, PassKind::Grayscale]
+        );
+        assert_eq!(passes[0].params, [2.0, 1.0, 0.0, 0.0]);
+        assert_eq!(passes[1].params, [2.0, 0.0, 1.0, 0.0]);
+        assert_eq!(passes[2].params, [1.5, 1.0, 0.0, 0.0]);
+        assert_eq!(passes[3].params[0], 1.0);
+        assert!(passes_for(&[]).is_empty());
+    }
+}
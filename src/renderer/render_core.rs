@@ -4,54 +4,194 @@
 //! code duplication between windowed and headless rendering modes.
 
 use std::collections::HashMap;
-use crate::renderable::{VertexProvider, Triangle, Quad, Cube, Circle, Cylinder, Cone, Sphere, Renderable};
+use std::ops::Range;
+use web_time::Instant;
+use crate::renderable::{Material, VertexProvider, Triangle, Quad, Cube, Circle, Cylinder, Cone, Sphere, Renderable};
 use crate::renderer::{
-    config::{RenderConfig, CullingMode}, 
+    background::BackgroundRenderer,
+    config::{RenderConfig, RenderMode, CullingMode},
+    debug_draw::{DebugDraw, DebugLineRenderer},
     dynamic_uniforms::DynamicUniformBuffer,
+    frustum::{transform_aabb, Frustum},
+    instance_buffer::{batch_by_key, batch_consecutive, InstanceBuffer, InstanceData},
+    lighting::{LightBuffer, Lighting},
+    picking::{PickError, PickResult, PickingRenderer},
+    texture::{TextureId, TextureStore},
     pipeline::RenderPipeline, 
-    vertex_cache::VertexBufferCache,
+    pipeline_cache::FillMode,
+    post_process::PostProcessor,
+    shader::{DebugShader, ShaderId},
+    shadow::{light_view_projection, ShadowRenderer},
+    skybox::SkyboxRenderer,
+    vertex_cache::{VertexBufferCache, VertexDataHash},
     camera::Camera,
 };
 
+/// Device features the renderer uses when the adapter has them
+///
+/// Without `POLYGON_MODE_LINE`, as on WebGL, [`RenderMode::Wireframe`] falls back to a shader.
+pub const OPTIONAL_FEATURES: wgpu::Features =
+    wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES.union(wgpu::Features::POLYGON_MODE_LINE);
+
+/// Shader and rasterization that replace the objects' own in a debug render mode,
+/// or `None` to draw them shaded
+fn debug_view(render_mode: RenderMode, line_polygons: bool) -> Option<(DebugShader, FillMode)> {
+    match render_mode {
+        RenderMode::Shaded => None,
+        RenderMode::Wireframe if line_polygons => Some((DebugShader::Unlit, FillMode::Lines)),
+        RenderMode::Wireframe => Some((DebugShader::BarycentricWireframe, FillMode::Solid)),
+        RenderMode::Points => Some((DebugShader::Unlit, FillMode::Points)),
+        RenderMode::Depth => Some((DebugShader::Depth, FillMode::Solid)),
+        RenderMode::Normals => Some((DebugShader::Normals, FillMode::Solid)),
+        RenderMode::Uvs => Some((DebugShader::Uvs, FillMode::Solid)),
+        RenderMode::ObjectIds => Some((DebugShader::ObjectIds, FillMode::Solid)),
+    }
+}
+
+/// Objects with the same shader, mesh and texture are drawn together as one instanced draw
+type BatchKey = (ShaderId, VertexDataHash, Option<TextureId>);
+
+/// Shadow casters only draw depth, so they are batched by mesh alone
+type ShadowBatch = (VertexDataHash, Range<u32>);
+
+/// Objects inside the camera's view, split between the opaque and transparent passes
+#[derive(Default)]
+struct VisibleObjects<'a> {
+    /// Opaque objects by culling mode, with their instances and materials in the same order
+    opaque_groups: Vec<(CullingMode, Vec<&'a dyn VertexProvider>)>,
+    opaque_instances: Vec<InstanceData>,
+    opaque_materials: Vec<Material>,
+    /// Objects with a translucent material, drawn after the opaque ones
+    transparent: Vec<TransparentObject<'a>>,
+}
+
+/// A translucent object waiting to be sorted into the transparent pass
+struct TransparentObject<'a> {
+    object: &'a dyn VertexProvider,
+    culling_mode: CullingMode,
+    instance: InstanceData,
+    material: Material,
+    /// Distance of the object's center in front of the camera
+    view_depth: f32,
+}
+
+/// Sort translucent objects farthest first, so each blends over everything behind it
+///
+/// Objects at the same depth keep a fixed order by entity id, so overlapping
+/// ones don't swap from frame to frame.
+fn sort_back_to_front(objects: &mut [TransparentObject]) {
+    objects.sort_by(|a, b| {
+        b.view_depth
+            .total_cmp(&a.view_depth)
+            .then(a.instance.pick_id.cmp(&b.instance.pick_id))
+    });
+}
+
+/// Shadow casters gathered while collecting the visible objects
+#[derive(Default)]
+struct ShadowCasters<'a> {
+    /// Every object casting shadows, whether or not the camera can see it, with its model matrix
+    objects: Vec<(&'a dyn VertexProvider, glam::Mat4)>,
+    /// World-space bounds of every submitted object, which the light's projection is fitted to
+    scene_bounds: Option<(glam::Vec3, glam::Vec3)>,
+}
+
+impl<'a> ShadowCasters<'a> {
+    fn add(&mut self, object: &'a dyn VertexProvider, matrix: glam::Mat4, bounds: (glam::Vec3, glam::Vec3), casts_shadows: bool) {
+        let (min, max) = bounds;
+        self.scene_bounds = Some(match self.scene_bounds {
+            Some((scene_min, scene_max)) => (scene_min.min(min), scene_max.max(max)),
+            None => (min, max),
+        });
+        if casts_shadows {
+            self.objects.push((object, matrix));
+        }
+    }
+}
+
+/// What the most recent render call submitted, culled and drew
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Objects passed in to be rendered
+    pub objects_submitted: usize,
+    /// Objects outside the camera's view, skipped before any buffers were prepared
+    pub objects_culled: usize,
+    /// Instanced draw calls issued for the remaining objects
+    pub draw_calls: usize,
+}
+
 /// Shared rendering logic and resources
 pub struct RenderCore {
     pub pipeline: RenderPipeline,
     pub uniform_buffer: DynamicUniformBuffer,
+    pub instance_buffer: InstanceBuffer,
+    pub background: BackgroundRenderer,
+    pub skybox: SkyboxRenderer,
+    pub shadows: ShadowRenderer,
+    pub picking: PickingRenderer,
+    /// Debug lines queued for the next frame
+    pub debug_draw: DebugDraw,
+    pub debug_lines: DebugLineRenderer,
+    pub post_processor: PostProcessor,
+    pub light_buffer: LightBuffer,
+    pub lighting: Lighting,
+    pub textures: TextureStore,
     pub vertex_cache: VertexBufferCache,
     pub camera: Camera,
+    pub stats: RenderStats,
+    /// When the core was created, the zero point of the shaders' `time`
+    pub start_time: Instant,
 }
 
+
 impl RenderCore {
     /// Create a new render core (windowed)
     #[cfg(feature = "windowing")]
     pub fn new_windowed(
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         format: wgpu::TextureFormat,
         width: u32,
         height: u32,
         config: RenderConfig,
     ) -> Self {
         let uniform_buffer = DynamicUniformBuffer::new(device);
-        
-        // Temporarily disable MSAA for windowed rendering to fix compatibility issue
-        let mut windowed_config = config;
-        windowed_config.antialiasing = crate::renderer::config::AntialiasingMode::None;
-        
+        let light_buffer = LightBuffer::new(device);
+        let textures = TextureStore::new(device, queue);
         let pipeline = RenderPipeline::new_with_config_core(
             device,
-            uniform_buffer.get_bind_group_layout(),
+            &[
+                uniform_buffer.get_bind_group_layout(),
+                light_buffer.get_bind_group_layout(),
+                textures.get_bind_group_layout(),
+            ],
             format,
-            windowed_config,
+            config,
             width,
             height,
         );
+        let picking = PickingRenderer::new(device, uniform_buffer.get_bind_group_layout(), width, height);
+        let debug_lines = DebugLineRenderer::new(device, uniform_buffer.get_bind_group_layout());
         let camera = Camera::new(width as f32 / height as f32);
 
         Self {
             pipeline,
             uniform_buffer,
+            instance_buffer: InstanceBuffer::new(device),
+            background: BackgroundRenderer::new(device),
+            skybox: SkyboxRenderer::new(device),
+            shadows: ShadowRenderer::new(device),
+            picking,
+            debug_draw: DebugDraw::new(),
+            debug_lines,
+            post_processor: PostProcessor::new(device, width, height),
+            light_buffer,
+            lighting: Lighting::default(),
+            textures,
             vertex_cache: VertexBufferCache::new(),
             camera,
+            stats: RenderStats::default(),
+            start_time: Instant::now(),
         }
     }
 
@@ -59,25 +199,47 @@ impl RenderCore {
     #[cfg(feature = "headless")]
     pub fn new_headless(
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         width: u32,
         height: u32,
         config: RenderConfig,
     ) -> anyhow::Result<Self> {
         let uniform_buffer = DynamicUniformBuffer::new(device);
+        let light_buffer = LightBuffer::new(device);
+        let textures = TextureStore::new(device, queue);
         let pipeline = RenderPipeline::new_headless_with_config(
             device,
-            uniform_buffer.get_bind_group_layout(),
+            &[
+                uniform_buffer.get_bind_group_layout(),
+                light_buffer.get_bind_group_layout(),
+                textures.get_bind_group_layout(),
+            ],
             width,
             height,
             config,
         )?;
+        let picking = PickingRenderer::new(device, uniform_buffer.get_bind_group_layout(), width, height);
+        let debug_lines = DebugLineRenderer::new(device, uniform_buffer.get_bind_group_layout());
         let camera = Camera::new(width as f32 / height as f32);
 
         Ok(Self {
             pipeline,
             uniform_buffer,
+            instance_buffer: InstanceBuffer::new(device),
+            background: BackgroundRenderer::new(device),
+            skybox: SkyboxRenderer::new(device),
+            shadows: ShadowRenderer::new(device),
+            picking,
+            debug_draw: DebugDraw::new(),
+            debug_lines,
+            post_processor: PostProcessor::new(device, width, height),
+            light_buffer,
+            lighting: Lighting::default(),
+            textures,
             vertex_cache: VertexBufferCache::new(),
             camera,
+            stats: RenderStats::default(),
+            start_time: Instant::now(),
         })
     }
 
@@ -100,99 +262,317 @@ impl RenderCore {
         spheres: &[&Sphere],
         should_clear: bool,
     ) -> Result<(), wgpu::SurfaceError> {
-        // Early exit if nothing to render
-        if triangles.is_empty() && quads.is_empty() && cubes.is_empty() && 
+        // Early exit if nothing to render; a clearing frame still runs so the background is drawn
+        if !should_clear && triangles.is_empty() && quads.is_empty() && cubes.is_empty() && 
            circles.is_empty() && cylinders.is_empty() && cones.is_empty() && 
-           spheres.is_empty() {
+           spheres.is_empty() && self.debug_draw.is_empty() {
+            self.stats = RenderStats::default();
             return Ok(());
         }
 
         // Clear frame data ONCE for the entire frame
         self.uniform_buffer.reset_frame();
 
-        // Extract bind group layout BEFORE any mutations
-        let bind_group_layout = self.uniform_buffer.get_bind_group_layout().clone();
+        // Outline every submitted object's mesh bounds along with the queued debug lines
+        self.debug_draw.add_bounds(triangles);
+        self.debug_draw.add_bounds(quads);
+        self.debug_draw.add_bounds(cubes);
+        self.debug_draw.add_bounds(circles);
+        self.debug_draw.add_bounds(cylinders);
+        self.debug_draw.add_bounds(cones);
+        self.debug_draw.add_bounds(spheres);
 
         // Group objects by culling mode for separate rendering passes
         let culling_groups = self.organize_by_culling_mode(triangles, quads, cubes, circles, cylinders, cones, spheres);
 
-        // Collect ALL objects and matrices across all culling groups
-        let (all_objects_by_group, all_matrices) = Self::collect_objects_and_matrices_static(&self.camera, &culling_groups);
+        // Collect ALL visible objects, instances and materials across all culling groups
+        // Objects outside the camera's view are dropped here, before any buffers are touched
+        // With shadows on, casters are gathered too, whether or not the camera can see them
+        let frustum = self.camera.frustum();
+        let mut shadow_casters = self.lighting.shadow_settings().map(|_| ShadowCasters::default());
+        let mut visible = Self::collect_objects_and_matrices_static(
+            &culling_groups,
+            &frustum,
+            self.camera.view_matrix,
+            shadow_casters.as_mut(),
+        );
+        sort_back_to_front(&mut visible.transparent);
+        let VisibleObjects {
+            opaque_groups: all_objects_by_group,
+            opaque_instances: all_instances,
+            opaque_materials: all_materials,
+            transparent,
+        } = visible;
+        let objects_submitted = triangles.len() + quads.len() + cubes.len() + circles.len()
+            + cylinders.len() + cones.len() + spheres.len();
+        let mut stats = RenderStats {
+            objects_submitted,
+            objects_culled: objects_submitted - all_instances.len() - transparent.len(),
+            draw_calls: 0,
+        };
 
-        // Create all vertex buffers and upload all uniforms
+        // Debug render modes swap every object's shader, and may rasterize only edges or vertices
+        let debug_view = debug_view(
+            self.pipeline.config.render_mode,
+            device.features().contains(wgpu::Features::POLYGON_MODE_LINE),
+        );
+        let debug_shader = debug_view.map(|(shader, _)| {
+            self.pipeline.cache.shaders().id(shader.name()).unwrap_or(ShaderId::BASIC)
+        });
+        let shader_for = |material: &Material| debug_shader.unwrap_or(material.shader);
+        // The barycentric wireframe needs each triangle's own three vertices
+        let unindexed = matches!(debug_view, Some((DebugShader::BarycentricWireframe, _)));
+
+        // Create any missing vertex buffers, keyed by geometry; translucent objects come last
         let all_objects: Vec<&dyn VertexProvider> = all_objects_by_group.iter()
             .flat_map(|(_, objects)| objects.iter().cloned())
+            .chain(transparent.iter().map(|transparent| transparent.object))
             .collect();
-        
-        let all_vertex_buffers = if !all_objects.is_empty() {
-            self.vertex_cache.get_or_create_mixed_buffers(&all_objects, device)
+        let mesh_keys = if unindexed {
+            self.vertex_cache.prepare_unindexed_buffers(&all_objects, device)
         } else {
-            Vec::new()
+            self.vertex_cache.prepare_mixed_buffers(&all_objects, device)
         };
+        let (mesh_keys, transparent_mesh_keys) = mesh_keys.split_at(all_instances.len());
 
-        let object_data = if !all_matrices.is_empty() {
-            self.uniform_buffer.upload_matrices(queue, &all_matrices)
+        // Within each culling group, objects sharing a shader, mesh and texture become one instanced draw
+        let mut instances: Vec<InstanceData> = Vec::with_capacity(all_instances.len() + transparent.len());
+        let mut group_batches = Vec::new();
+        let mut object_index = 0;
+        for (culling_mode, group_objects) in &all_objects_by_group {
+            let group_start = object_index;
+            object_index += group_objects.len();
+
+            let batch_keys: Vec<BatchKey> = (group_start..object_index)
+                .map(|index| (shader_for(&all_materials[index]), mesh_keys[index], all_materials[index].texture))
+                .collect();
+            let mut batches: Vec<(BatchKey, Range<u32>)> = batch_by_key(&batch_keys)
+                .into_iter()
+                .map(|(key, members)| {
+                    let first_instance = instances.len() as u32;
+                    instances.extend(members.iter().map(|member| all_instances[group_start + member]));
+                    (key, first_instance..instances.len() as u32)
+                })
+                .collect();
+            // Keep each shader's batches together so its pipeline is bound once per group
+            batches.sort_by_key(|((shader, _, _), _)| *shader);
+            group_batches.push((*culling_mode, batches));
+        }
+
+        // Translucent objects keep their back-to-front order, so only neighbours sharing everything are instanced together
+        let transparent_keys: Vec<(CullingMode, BatchKey)> = transparent.iter()
+            .zip(transparent_mesh_keys)
+            .map(|(object, mesh_key)| (object.culling_mode, (shader_for(&object.material), *mesh_key, object.material.texture)))
+            .collect();
+        let transparent_batches: Vec<((CullingMode, BatchKey), Range<u32>)> = batch_consecutive(&transparent_keys)
+            .into_iter()
+            .map(|(key, members)| {
+                let first_instance = instances.len() as u32;
+                instances.extend(transparent[members].iter().map(|object| object.instance));
+                (key, first_instance..instances.len() as u32)
+            })
+            .collect();
+
+        // Shadow casters follow the visible objects in the instance buffer
+        let (shadow_view_proj, shadow_batches) = match &shadow_casters {
+            Some(casters) => match self.prepare_shadow_casters(device, queue, casters, &mut instances) {
+                Some((light_view_proj, batches)) => (Some(light_view_proj), batches),
+                None => (None, Vec::new()),
+            },
+            None => (None, Vec::new()),
+        };
+
+        self.instance_buffer.upload(device, queue, &instances);
+        let view_offsets = self.uniform_buffer.upload_matrices(device, queue, &[self.camera.get_view_projection_matrix()]);
+        self.light_buffer.update(
+            queue,
+            &self.lighting,
+            self.camera.position,
+            self.start_time.elapsed().as_secs_f32(),
+            shadow_view_proj,
+        );
+        let uniform_bind_group = self.uniform_buffer.bind_group();
+        let instance_buffer = self.instance_buffer.buffer();
+
+        // Make sure the pipeline for every culling mode and shader is built before rendering
+        // They share the base config, so every pipeline uses the same sample count
+        let base_key = self.pipeline.base_key().with_fill(debug_view.map_or(FillMode::Solid, |(_, fill)| fill));
+        for (culling_mode, batches) in &group_batches {
+            for ((shader, _, _), _) in batches {
+                self.pipeline.cache.get_or_create(device, base_key.with_culling(*culling_mode).with_shader(*shader));
+            }
+        }
+        for ((culling_mode, (shader, _, _)), _) in &transparent_batches {
+            self.pipeline.cache.get_or_create(device, base_key.with_culling(*culling_mode).with_shader(*shader).transparent());
+        }
+
+        // Gradient and sky backgrounds are drawn at the start of the pass, so only when clearing
+        let background = self.pipeline.config.background;
+        let draw_background = should_clear && self.background.prepare(
+            device,
+            queue,
+            &background,
+            base_key.format,
+            base_key.sample_count,
+        );
+        let draw_sky = should_clear && self.skybox.prepare(
+            device,
+            queue,
+            &background,
+            &self.camera,
+            base_key.format,
+            base_key.sample_count,
+        );
+
+        // Debug lines are uploaded once and then flushed, so each frame queues its own
+        let draw_lines = self.debug_lines.prepare(device, queue, &self.debug_draw, base_key.format, base_key.sample_count);
+        self.debug_draw.clear();
+
+        // With post-processing or HDR, objects are drawn offscreen and the effect chain writes the target view
+        let post_processing = self.post_processor.prepare(device, queue, &self.pipeline.config, self.pipeline.format);
+        let frame_view = if post_processing {
+            self.post_processor.scene_view()
         } else {
-            Vec::new()
+            target_view
         };
 
-        // Extract everything needed for rendering before creating pipelines
-        let pipeline_config = self.pipeline.config.clone();
-        let default_pipeline = &self.pipeline.pipeline;
-
-        // Create pipelines for all culling modes BEFORE rendering (skip MSAA for now)
-        let mut pipelines: HashMap<CullingMode, wgpu::RenderPipeline> = HashMap::new();
-        for (culling_mode, _) in &all_objects_by_group {
-            if *culling_mode != pipeline_config.culling {
-                // Create a temporary config without MSAA for compatibility
-                let mut temp_config = pipeline_config.clone();
-                temp_config.antialiasing = crate::renderer::config::AntialiasingMode::None;
-                
-                let pipeline = RenderPipeline::create_culling_pipeline(
-                    device,
-                    &bind_group_layout,
-                    self.pipeline.get_format(),
-                    &temp_config,
-                    *culling_mode,
-                );
-                pipelines.insert(*culling_mode, pipeline);
+        // Multisampled rendering draws into the core's MSAA targets and resolves into the frame view
+        let multisampled_view = multisampled_view.or(self.pipeline.multisampled_framebuffer.as_ref());
+        let depth_view = if multisampled_view.is_some() {
+            self.pipeline.multisampled_depth.as_ref().unwrap_or(depth_view)
+        } else {
+            depth_view
+        };
+
+        // Sort groups by pipeline key so each pipeline is bound once
+        group_batches.sort_by_key(|(culling_mode, _)| *culling_mode);
+
+        // The shadow map is drawn first, so the main pass can sample it
+        if !shadow_batches.is_empty()
+            && let Some(mut shadow_pass) = self.shadows.begin_pass(encoder)
+        {
+            shadow_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            for (key, instances) in &shadow_batches {
+                if let Some(mesh) = self.vertex_cache.get(*key) {
+                    mesh.draw(&mut shadow_pass, instances.clone());
+                }
             }
         }
 
-        // Now render each culling group using all pre-computed data
-        let mut first_group = should_clear;
-        let mut object_index = 0;
-        for (culling_mode, group_objects) in all_objects_by_group {
-            let group_size = group_objects.len();
-            if group_size > 0 {
-                // Select the appropriate pipeline
-                let pipeline = if let Some(temp_pipeline) = pipelines.get(&culling_mode) {
-                    temp_pipeline
-                } else {
-                    default_pipeline
-                };
-
-                // Render this group using pre-computed data
-                Self::render_culling_group_static(
-                    encoder,
-                    target_view,
-                    depth_view,
-                    pipeline,
-                    multisampled_view,
-                    &all_vertex_buffers[object_index..object_index + group_size],
-                    &object_data[object_index..object_index + group_size],
-                    first_group,
-                    culling_mode,
-                    &pipeline_config,
-                )?;
-                first_group = false;
-                object_index += group_size;
+        // Render every group in a single pass, switching pipelines between groups
+        {
+            let mut render_pass = Self::begin_frame_pass_static(
+                encoder,
+                frame_view,
+                depth_view,
+                multisampled_view,
+                should_clear.then(|| background.clear_color()),
+            );
+            if draw_background {
+                self.background.draw(&mut render_pass, base_key.format, base_key.sample_count);
+            }
+            if draw_sky {
+                self.skybox.draw(&mut render_pass, base_key.format, base_key.sample_count);
+            }
+
+            render_pass.set_bind_group(0, uniform_bind_group, &[view_offsets[0]]);
+            render_pass.set_bind_group(1, self.light_buffer.bind_group(), &[]);
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+
+            for (culling_mode, batches) in &group_batches {
+                // One instanced draw per shader, mesh and texture, switching pipelines between shaders
+                let mut bound_shader = None;
+                for ((shader, key, texture), instances) in batches {
+                    if bound_shader != Some(*shader) {
+                        let pipeline = self.pipeline.cache.get(&base_key.with_culling(*culling_mode).with_shader(*shader))
+                            .expect("pipelines are built for every culling group and shader before rendering");
+                        render_pass.set_pipeline(pipeline);
+                        bound_shader = Some(*shader);
+                    }
+
+                    if let Some(mesh) = self.vertex_cache.get(*key) {
+                        render_pass.set_bind_group(2, self.textures.bind_group(*texture), &[]);
+                        if unindexed {
+                            mesh.draw_unindexed(&mut render_pass, instances.clone());
+                        } else {
+                            mesh.draw(&mut render_pass, instances.clone());
+                        }
+                        stats.draw_calls += 1;
+                    }
+                }
+
+                log::debug!("🎨 Rendered {} meshes with culling mode {:?}", batches.len(), culling_mode);
+            }
+
+            // Translucent objects go last, blending over everything drawn so far without hiding what's behind them
+            let mut bound_key = None;
+            for ((culling_mode, (shader, key, texture)), instances) in &transparent_batches {
+                let pipeline_key = base_key.with_culling(*culling_mode).with_shader(*shader).transparent();
+                if bound_key != Some(pipeline_key) {
+                    let pipeline = self.pipeline.cache.get(&pipeline_key)
+                        .expect("pipelines are built for every transparent batch before rendering");
+                    render_pass.set_pipeline(pipeline);
+                    bound_key = Some(pipeline_key);
+                }
+
+                if let Some(mesh) = self.vertex_cache.get(*key) {
+                    render_pass.set_bind_group(2, self.textures.bind_group(*texture), &[]);
+                    if unindexed {
+                        mesh.draw_unindexed(&mut render_pass, instances.clone());
+                    } else {
+                        mesh.draw(&mut render_pass, instances.clone());
+                    }
+                    stats.draw_calls += 1;
+                }
+            }
+
+            // Debug lines go over everything, still hidden behind nearer objects
+            if draw_lines {
+                self.debug_lines.draw(&mut render_pass, base_key.format, base_key.sample_count);
             }
         }
 
+        // Entity ids go into their own single-sampled pass, since integer targets can't be resolved
+        if self.pipeline.config.picking {
+            let culling_modes = group_batches.iter().map(|(culling_mode, _)| *culling_mode)
+                .chain(transparent_batches.iter().map(|((culling_mode, _), _)| *culling_mode));
+            self.picking.prepare(device, culling_modes);
+            if let Some(mut pick_pass) = self.picking.begin_pass(encoder, should_clear) {
+                pick_pass.set_bind_group(0, uniform_bind_group, &[view_offsets[0]]);
+                pick_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+                for (culling_mode, batches) in &group_batches {
+                    let pipeline = self.picking.pipeline(*culling_mode)
+                        .expect("pick pipelines are built for every culling group before rendering");
+                    pick_pass.set_pipeline(pipeline);
+                    for ((_, key, _), instances) in batches {
+                        if let Some(mesh) = self.vertex_cache.get(*key) {
+                            mesh.draw(&mut pick_pass, instances.clone());
+                        }
+                    }
+                }
+                // Translucent objects are picked like opaque ones, by whichever is nearest
+                for ((culling_mode, (_, key, _)), instances) in &transparent_batches {
+                    let pipeline = self.picking.pipeline(*culling_mode)
+                        .expect("pick pipelines are built for every culling group before rendering");
+                    pick_pass.set_pipeline(pipeline);
+                    if let Some(mesh) = self.vertex_cache.get(*key) {
+                        mesh.draw(&mut pick_pass, instances.clone());
+                    }
+                }
+            }
+        } else {
+            self.picking.release();
+        }
+
+        if post_processing {
+            self.post_processor.run(encoder, target_view);
+        }
+
         // Periodic cache cleanup
         self.vertex_cache.cleanup_old_entries();
 
+        self.stats = stats;
+
         Ok(())
     }
 
@@ -254,140 +634,192 @@ impl RenderCore {
         culling_groups
     }
 
-    /// Collect objects and calculate transformation matrices (static version to avoid borrowing issues)
+    /// Collect the objects inside `frustum` with their instance data and materials (static version to avoid borrowing issues)
+    /// Translucent objects are set aside, with their depth along `view`, for the transparent pass.
+    /// Every shadow caster is added to `shadow_casters`, if given, even outside the frustum
     fn collect_objects_and_matrices_static<'a>(
-        camera: &Camera,
         culling_groups: &'a HashMap<CullingMode, (Vec<&'a Triangle>, Vec<&'a Quad>, Vec<&'a Cube>, Vec<&'a Circle>, Vec<&'a Cylinder>, Vec<&'a Cone>, Vec<&'a Sphere>)>,
-    ) -> (Vec<(CullingMode, Vec<&'a dyn VertexProvider>)>, Vec<glam::Mat4>) {
-        let mut all_objects_by_group: Vec<(CullingMode, Vec<&dyn VertexProvider>)> = Vec::new();
-        let mut all_matrices: Vec<glam::Mat4> = Vec::new();
+        frustum: &Frustum,
+        view: glam::Mat4,
+        mut shadow_casters: Option<&mut ShadowCasters<'a>>,
+    ) -> VisibleObjects<'a> {
+        let mut visible = VisibleObjects::default();
 
         for (culling_mode, (group_triangles, group_quads, group_cubes, group_circles, group_cylinders, group_cones, group_spheres)) in culling_groups {
-            if group_triangles.is_empty() && group_quads.is_empty() && group_cubes.is_empty() && 
-               group_circles.is_empty() && group_cylinders.is_empty() && group_cones.is_empty() && 
-               group_spheres.is_empty() {
+            let mut group_objects: Vec<&dyn VertexProvider> = Vec::new();
+
+            // Add instances, materials and objects in the same order
+            Self::collect_visible_static(group_triangles, *culling_mode, frustum, view, &mut group_objects, &mut visible, shadow_casters.as_deref_mut());
+            Self::collect_visible_static(group_quads, *culling_mode, frustum, view, &mut group_objects, &mut visible, shadow_casters.as_deref_mut());
+            Self::collect_visible_static(group_cubes, *culling_mode, frustum, view, &mut group_objects, &mut visible, shadow_casters.as_deref_mut());
+            Self::collect_visible_static(group_circles, *culling_mode, frustum, view, &mut group_objects, &mut visible, shadow_casters.as_deref_mut());
+            Self::collect_visible_static(group_cylinders, *culling_mode, frustum, view, &mut group_objects, &mut visible, shadow_casters.as_deref_mut());
+            Self::collect_visible_static(group_cones, *culling_mode, frustum, view, &mut group_objects, &mut visible, shadow_casters.as_deref_mut());
+            Self::collect_visible_static(group_spheres, *culling_mode, frustum, view, &mut group_objects, &mut visible, shadow_casters.as_deref_mut());
+
+            if group_objects.is_empty() {
                 continue;
             }
+            visible.opaque_groups.push((*culling_mode, group_objects));
+        }
 
-            let mut group_objects: Vec<&dyn VertexProvider> = Vec::new();
-            
-            // Add matrices and objects in the same order
-            for mut triangle in group_triangles {
-                all_matrices.push(camera.get_view_projection_matrix() * triangle.get_matrix_cached());
-                group_objects.push(*triangle);
-            }
-            for mut quad in group_quads {
-                all_matrices.push(camera.get_view_projection_matrix() * quad.get_matrix_cached());
-                group_objects.push(*quad);
-            }
-            for mut cube in group_cubes {
-                all_matrices.push(camera.get_view_projection_matrix() * cube.get_matrix_cached());
-                group_objects.push(*cube);
-            }
-            for mut circle in group_circles {
-                all_matrices.push(camera.get_view_projection_matrix() * circle.get_matrix_cached());
-                group_objects.push(*circle);
-            }
-            for mut cylinder in group_cylinders {
-                all_matrices.push(camera.get_view_projection_matrix() * cylinder.get_matrix_cached());
-                group_objects.push(*cylinder);
+        visible
+    }
+
+    /// Add each object whose transformed bounds intersect `frustum` to `visible`, with its instance data and material
+    /// Opaque objects also go in `group_objects`, for the group with `culling_mode`
+    fn collect_visible_static<'a, T: Renderable + VertexProvider>(
+        objects: &[&'a T],
+        culling_mode: CullingMode,
+        frustum: &Frustum,
+        view: glam::Mat4,
+        group_objects: &mut Vec<&'a dyn VertexProvider>,
+        visible: &mut VisibleObjects<'a>,
+        mut shadow_casters: Option<&mut ShadowCasters<'a>>,
+    ) {
+        for object in objects {
+            let matrix = object.get_matrix();
+            let (min, max) = object.bounds();
+            let material = object.get_material();
+            if let Some(casters) = shadow_casters.as_deref_mut() {
+                casters.add(*object, matrix, transform_aabb(min, max, matrix), material.casts_shadows);
             }
-            for mut cone in group_cones {
-                all_matrices.push(camera.get_view_projection_matrix() * cone.get_matrix_cached());
-                group_objects.push(*cone);
+
+            if !frustum.intersects_transformed_aabb(min, max, matrix) {
+                continue;
             }
-            for mut sphere in group_spheres {
-                all_matrices.push(camera.get_view_projection_matrix() * sphere.get_matrix_cached());
-                group_objects.push(*sphere);
+            let instance = InstanceData::new(matrix, &material).with_entity_id(object.entity_id());
+            if material.is_transparent() {
+                let (world_min, world_max) = transform_aabb(min, max, matrix);
+                visible.transparent.push(TransparentObject {
+                    object: *object,
+                    culling_mode,
+                    instance,
+                    material,
+                    // The camera looks down -Z in view space
+                    view_depth: -view.transform_point3((world_min + world_max) * 0.5).z,
+                });
+            } else {
+                visible.opaque_instances.push(instance);
+                visible.opaque_materials.push(material);
+                group_objects.push(*object);
             }
+        }
+    }
 
-            all_objects_by_group.push((*culling_mode, group_objects));
+    /// Fit the light's projection around the scene and batch the shadow casters by mesh,
+    /// appending their instances to `instances`
+    ///
+    /// Returns the light's view-projection and the batches, or `None` when there are no shadows to draw.
+    fn prepare_shadow_casters(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        casters: &ShadowCasters,
+        instances: &mut Vec<InstanceData>,
+    ) -> Option<(glam::Mat4, Vec<ShadowBatch>)> {
+        let light = self.lighting.directional?;
+        let settings = light.shadows?;
+        let (min, max) = casters.scene_bounds?;
+        if casters.objects.is_empty() {
+            return None;
         }
 
-        (all_objects_by_group, all_matrices)
+        if self.shadows.ensure_map(device, settings.resolution)
+            && let Some(shadow_map) = self.shadows.map_view()
+        {
+            self.light_buffer.set_shadow_map(device, shadow_map);
+        }
+        let light_view_proj = light_view_projection(light.direction, min, max);
+        self.shadows.prepare(queue, light_view_proj);
+
+        // Only depth is drawn, so casters sharing a mesh share a draw regardless of their material
+        let objects: Vec<&dyn VertexProvider> = casters.objects.iter().map(|(object, _)| *object).collect();
+        let mesh_keys = self.vertex_cache.prepare_mixed_buffers(&objects, device);
+        let batches = batch_by_key(&mesh_keys)
+            .into_iter()
+            .map(|(key, members)| {
+                let first_instance = instances.len() as u32;
+                instances.extend(members.iter().map(|member| InstanceData::from_matrix(casters.objects[*member].1)));
+                (key, first_instance..instances.len() as u32)
+            })
+            .collect();
+        Some((light_view_proj, batches))
     }
 
-    /// Static rendering method that doesn't require borrowing self
-    fn render_culling_group_static(
-        encoder: &mut wgpu::CommandEncoder,
+    /// Begin the frame's render pass, clearing to `clear_color` or loading the existing contents
+    fn begin_frame_pass_static<'e>(
+        encoder: &'e mut wgpu::CommandEncoder,
         view: &wgpu::TextureView,
         depth_view: &wgpu::TextureView,
-        pipeline: &wgpu::RenderPipeline,
-        _multisampled_framebuffer: Option<&wgpu::TextureView>,
-        vertex_buffers: &[(&wgpu::Buffer, u32)],
-        uniform_data: &[(&wgpu::BindGroup, u32)],
-        should_clear: bool,
-        culling_mode: CullingMode,
-        config: &RenderConfig,
-    ) -> Result<(), wgpu::SurfaceError> {
-        if vertex_buffers.is_empty() || uniform_data.is_empty() {
-            return Ok(());
-        }
-
-        let (color_attachment_view, resolve_target) = if config.antialiasing.is_multisampled() {
-            // In a real implementation, we would need access to the multisampled framebuffer
-            // For now, fall back to direct rendering to avoid borrowing conflicts
-            (view, None)
-        } else {
-            (view, None)
+        multisampled_framebuffer: Option<&wgpu::TextureView>,
+        clear_color: Option<wgpu::Color>,
+    ) -> wgpu::RenderPass<'e> {
+        // With MSAA, draw into the multisampled framebuffer and resolve into the target view.
+        // The multisampled contents are stored so later renders in the same frame can draw on top.
+        let (color_attachment_view, resolve_target) = match multisampled_framebuffer {
+            Some(multisampled_view) => (multisampled_view, Some(view)),
+            None => (view, None),
         };
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Culling Group Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: color_attachment_view,
-                    resolve_target,
-                    ops: wgpu::Operations {
-                        load: if should_clear {
-                            wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.1,
-                                g: 0.2,
-                                b: 0.3,
-                                a: 1.0,
-                            })
-                        } else {
-                            wgpu::LoadOp::Load
-                        },
-                        store: wgpu::StoreOp::Store,
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Frame Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_attachment_view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: match clear_color {
+                        Some(color) => wgpu::LoadOp::Clear(color),
+                        None => wgpu::LoadOp::Load,
                     },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: if should_clear {
-                            wgpu::LoadOp::Clear(1.0)
-                        } else {
-                            wgpu::LoadOp::Load
-                        },
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: if clear_color.is_some() {
+                        wgpu::LoadOp::Clear(1.0)
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
+                    store: wgpu::StoreOp::Store,
                 }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            render_pass.set_pipeline(pipeline);
-
-            // Render each object
-            for (index, vertex_buffer) in vertex_buffers.iter().enumerate() {
-                if index < uniform_data.len() {
-                    let (bind_group, dynamic_offset) = &uniform_data[index];
-
-                    render_pass.set_vertex_buffer(0, vertex_buffer.0.slice(..));
-                    render_pass.set_bind_group(0, *bind_group, &[*dynamic_offset]);
-                    render_pass.draw(0..vertex_buffer.1, 0..1);
-                }
-            }
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+    }
 
-            // Render pass is automatically dropped here
+    /// Read back the entity and depth at pixel (`x`, `y`) of the last frame, counted from the top-left corner
+    pub async fn pick(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        x: u32,
+        y: u32,
+    ) -> Result<Option<PickResult>, PickError> {
+        if !self.pipeline.config.picking {
+            return Err(PickError::Disabled);
         }
+        self.picking.pick(device, queue, x, y).await
+    }
 
-        log::debug!("🎨 Rendered {} objects with culling mode {:?}", vertex_buffers.len(), culling_mode);
+    /// Get the debug lines queued for the next frame
+    pub fn debug_draw(&self) -> &DebugDraw {
+        &self.debug_draw
+    }
 
-        Ok(())
+    /// Get mutable debug lines, for queueing lines, arrows, grids, axes and boxes for the next frame
+    pub fn debug_draw_mut(&mut self) -> &mut DebugDraw {
+        &mut self.debug_draw
+    }
+
+    /// Get what the most recent render call submitted, culled and drew
+    pub fn render_stats(&self) -> RenderStats {
+        self.stats
     }
 
     /// Get renderer performance statistics
@@ -411,7 +843,6 @@ impl RenderCore {
     ) {
         self.pipeline.update_config(
             device,
-            self.uniform_buffer.get_bind_group_layout(),
             format,
             config,
             width,
@@ -434,8 +865,59 @@ impl RenderCore {
         &mut self.camera
     }
 
-    /// Resize the render target
-    pub fn resize(&mut self, width: u32, height: u32) {
+    /// Get the scene's lights
+    pub fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+
+    /// Get mutable lights for adding, removing or adjusting them
+    pub fn lighting_mut(&mut self) -> &mut Lighting {
+        &mut self.lighting
+    }
+
+    /// Resize the render target, recreating any multisampled attachments
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
         self.camera.set_aspect_ratio(width as f32 / height as f32);
+        self.pipeline.resize(device, width, height);
+        self.post_processor.resize(width, height);
+        self.picking.resize(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::EntityId;
+
+    #[test]
+    fn test_transparent_objects_sort_back_to_front() {
+        let triangle = Triangle::new();
+        let transparent = |view_depth: f32, entity_id: EntityId| TransparentObject {
+            object: &triangle,
+            culling_mode: CullingMode::None,
+            instance: InstanceData::from_matrix(glam::Mat4::IDENTITY).with_entity_id(Some(entity_id)),
+            material: Material::default().with_opacity(0.5),
+            view_depth,
+        };
+
+        let mut objects = vec![transparent(2.0, 3), transparent(5.0, 1), transparent(2.0, 0), transparent(-1.0, 2)];
+        sort_back_to_front(&mut objects);
+        let order: Vec<(f32, u32)> = objects.iter().map(|object| (object.view_depth, object.instance.pick_id)).collect();
+        assert_eq!(order, vec![(5.0, 2), (2.0, 1), (2.0, 4), (-1.0, 3)]);
+    }
+
+    #[test]
+    fn test_wireframe_falls_back_to_shader_without_line_polygons() {
+        assert_eq!(debug_view(RenderMode::Shaded, true), None);
+        assert_eq!(debug_view(RenderMode::Wireframe, true), Some((DebugShader::Unlit, FillMode::Lines)));
+        assert_eq!(
+            debug_view(RenderMode::Wireframe, false),
+            Some((DebugShader::BarycentricWireframe, FillMode::Solid))
+        );
+        // Only the wireframe depends on line support
+        for mode in [RenderMode::Points, RenderMode::Depth, RenderMode::Normals, RenderMode::Uvs, RenderMode::ObjectIds] {
+            assert_eq!(debug_view(mode, true), debug_view(mode, false));
+            assert_ne!(debug_view(mode, true).map(|(_, fill)| fill), Some(FillMode::Lines));
+        }
     }
 }
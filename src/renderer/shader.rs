@@ -0,0 +1,593 @@
+//! Named WGSL shader modules
+//!
+//! The built-in shader is registered as [`BASIC_SHADER_NAME`]. Custom shaders
+//! are registered under their own names and selected per object through
+//! [`Material::shader`](crate::renderable::Material::shader). They are drawn
+//! with the same vertex buffers and bind groups as the built-in shader, so a
+//! custom shader has to follow this contract:
+//!
+//! - Entry points are `vs_main` (vertex) and `fs_main` (fragment), and the
+//!   fragment shader writes a single color to `@location(0)`.
+//! - Per-vertex inputs: `@location(0) position: vec3<f32>`,
+//!   `@location(1) color: vec3<f32>`, `@location(2) normal: vec3<f32>` and
+//!   `@location(3) uv: vec2<f32>`, all in model space.
+//! - Per-instance inputs: the model matrix columns at `@location(8)` through
+//!   `@location(11)` as `vec4<f32>`, then the material's base color and
+//!   opacity at `@location(12)`, emissive color and roughness at
+//!   `@location(13)`, and flags at `@location(14)` (`x` is 1 when vertex
+//!   colors are used, `y` is 1 when the object receives shadows). The
+//!   picking id at `@location(15)` is a `u32` that shaders may leave undeclared.
+//! - `@group(0) @binding(0)` is the camera's `view_proj: mat4x4<f32>`,
+//!   visible to the vertex stage. The clip position is
+//!   `view_proj * model * vec4<f32>(position, 1.0)`.
+//! - `@group(1) @binding(0)` is the light block declared in `basic.wgsl`,
+//!   including `time`, the seconds since the renderer was created. The
+//!   directional light's shadow map is at `@binding(1)` as a
+//!   `texture_depth_2d`, with its `sampler_comparison` at `@binding(2)`, both
+//!   visible to the fragment stage.
+//! - `@group(2)` holds the material's texture at binding 0 and its sampler at
+//!   binding 1, visible to the fragment stage.
+//!
+//! Shaders may leave out any input or binding they don't use. Sources are
+//! checked against this contract when they're registered, so a broken shader
+//! is reported as a [`ShaderError`] instead of failing later when a pipeline
+//! is built.
+//!
+//! The debug views behind [`RenderMode`](crate::renderer::RenderMode) are
+//! registered alongside the built-in shader, under the names of the
+//! [`DebugShader`] variants.
+
+use std::collections::HashMap;
+
+use crate::renderer::lighting::LIGHT_UNIFORM_SIZE;
+
+/// Name the built-in shader is registered under
+pub const BASIC_SHADER_NAME: &str = "basic";
+
+/// Vertex and fragment entry points every shader must provide
+pub const VERTEX_ENTRY_POINT: &str = "vs_main";
+pub const FRAGMENT_ENTRY_POINT: &str = "fs_main";
+
+/// Built-in shaders that replace the objects' own for the debug render modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugShader {
+    /// Base color without lighting, for line and point rasterization
+    Unlit,
+    /// Base color on triangle edges only, for devices without line rasterization
+    ///
+    /// Needs every three vertices to be their own triangle, so meshes are drawn unindexed.
+    BarycentricWireframe,
+    Depth,
+    Normals,
+    Uvs,
+    ObjectIds,
+}
+
+impl DebugShader {
+    pub const ALL: [DebugShader; 6] = [
+        DebugShader::Unlit,
+        DebugShader::BarycentricWireframe,
+        DebugShader::Depth,
+        DebugShader::Normals,
+        DebugShader::Uvs,
+        DebugShader::ObjectIds,
+    ];
+
+    /// Name the shader is registered under
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugShader::Unlit => "debug_unlit",
+            DebugShader::BarycentricWireframe => "debug_wireframe",
+            DebugShader::Depth => "debug_depth",
+            DebugShader::Normals => "debug_normals",
+            DebugShader::Uvs => "debug_uvs",
+            DebugShader::ObjectIds => "debug_object_ids",
+        }
+    }
+
+    /// WGSL source, `debug.wgsl` with the view it draws selected
+    pub fn source(&self) -> String {
+        // Matches the DEBUG_VIEW cases in debug.wgsl
+        let view = match self {
+            DebugShader::Unlit => 0,
+            DebugShader::BarycentricWireframe => 1,
+            DebugShader::Depth => 2,
+            DebugShader::Normals => 3,
+            DebugShader::Uvs => 4,
+            DebugShader::ObjectIds => 5,
+        };
+        format!("const DEBUG_VIEW: u32 = {}u;\n{}", view, include_str!("../shaders/debug.wgsl"))
+    }
+}
+
+/// Handle to a shader registered with a [`ShaderRegistry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShaderId(u32);
+
+impl ShaderId {
+    /// The built-in shader, which every registry has
+    pub const BASIC: ShaderId = ShaderId(0);
+}
+
+impl Default for ShaderId {
+    fn default() -> Self {
+        ShaderId::BASIC
+    }
+}
+
+/// Errors produced when a shader source can't be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderError {
+    /// The source isn't valid WGSL
+    Parse { name: String, message: String },
+    /// The source parsed but failed validation
+    Validation { name: String, message: String },
+    /// A required entry point is missing or has the wrong stage
+    MissingEntryPoint { name: String, entry_point: &'static str },
+    /// The entry points use inputs, bindings or outputs the renderer doesn't provide
+    Interface { name: String, message: String },
+    /// wgpu rejected the shader or a pipeline built from it
+    Pipeline { name: String, message: String },
+}
+
+impl std::fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderError::Parse { name, message } => write!(f, "Shader '{}' failed to parse:\n{}", name, message),
+            ShaderError::Validation { name, message } => {
+                write!(f, "Shader '{}' failed validation:\n{}", name, message)
+            }
+            ShaderError::MissingEntryPoint { name, entry_point } => {
+                write!(f, "Shader '{}' has no '{}' entry point", name, entry_point)
+            }
+            ShaderError::Interface { name, message } => {
+                write!(f, "Shader '{}' breaks the shader contract: {}", name, message)
+            }
+            ShaderError::Pipeline { name, message } => {
+                write!(f, "Shader '{}' failed to build a pipeline:\n{}", name, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Check that a WGSL source compiles and has the entry points pipelines need
+pub fn validate_wgsl(name: &str, source: &str) -> Result<(), ShaderError> {
+    let (module, info) = parse_and_validate(name, source)?;
+
+    for (entry_point, stage) in [
+        (VERTEX_ENTRY_POINT, naga::ShaderStage::Vertex),
+        (FRAGMENT_ENTRY_POINT, naga::ShaderStage::Fragment),
+    ] {
+        if !module.entry_points.iter().any(|entry| entry.name == entry_point && entry.stage == stage) {
+            return Err(ShaderError::MissingEntryPoint {
+                name: name.to_string(),
+                entry_point,
+            });
+        }
+    }
+
+    check_contract(&module, &info).map_err(|message| ShaderError::Interface {
+        name: name.to_string(),
+        message,
+    })
+}
+
+/// Parse a WGSL source and run naga's validation, without checking it against the shader contract
+fn parse_and_validate(name: &str, source: &str) -> Result<(naga::Module, naga::valid::ModuleInfo), ShaderError> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|error| ShaderError::Parse {
+        name: name.to_string(),
+        message: error.emit_to_string(source),
+    })?;
+
+    let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::default())
+        .validate(&module)
+        .map_err(|error| ShaderError::Validation {
+            name: name.to_string(),
+            message: error.emit_to_string(source),
+        })?;
+
+    Ok((module, info))
+}
+
+/// Type of the per-vertex or per-instance input the renderer provides at a location,
+/// as its scalar kind and component count
+fn vertex_input(location: u32) -> Option<(naga::ScalarKind, u8)> {
+    use naga::ScalarKind::{Float, Uint};
+    match location {
+        0..=2 => Some((Float, 3)),
+        3 => Some((Float, 2)),
+        8..=14 => Some((Float, 4)),
+        15 => Some((Uint, 1)),
+        _ => None,
+    }
+}
+
+/// Scalar kind and component count of a 32-bit scalar or vector
+fn numeric_type(inner: &naga::TypeInner) -> Option<(naga::ScalarKind, u8)> {
+    match *inner {
+        naga::TypeInner::Scalar(scalar) if scalar.width == 4 => Some((scalar.kind, 1)),
+        naga::TypeInner::Vector { size, scalar } if scalar.width == 4 => Some((scalar.kind, size as u8)),
+        _ => None,
+    }
+}
+
+fn numeric_type_name((kind, components): (naga::ScalarKind, u8)) -> String {
+    let scalar = match kind {
+        naga::ScalarKind::Float => "f32",
+        naga::ScalarKind::Uint => "u32",
+        naga::ScalarKind::Sint => "i32",
+        _ => "bool",
+    };
+    if components == 1 { scalar.to_string() } else { format!("vec{}<{}>", components, scalar) }
+}
+
+/// Every `@location` of an argument or result with its type, looking inside structs
+fn locations(module: &naga::Module, ty: naga::Handle<naga::Type>, binding: Option<&naga::Binding>) -> Vec<(u32, naga::Handle<naga::Type>)> {
+    match binding {
+        Some(naga::Binding::Location { location, .. }) => vec![(*location, ty)],
+        Some(naga::Binding::BuiltIn(_)) => Vec::new(),
+        None => match &module.types[ty].inner {
+            naga::TypeInner::Struct { members, .. } => members
+                .iter()
+                .flat_map(|member| locations(module, member.ty, member.binding.as_ref()))
+                .collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+/// A resource the renderer binds for every pipeline
+#[derive(Debug, Clone, Copy)]
+enum ProvidedBinding {
+    /// A uniform buffer holding `size` bytes
+    Uniform { size: u64, vertex: bool, fragment: bool },
+    DepthTexture,
+    ComparisonSampler,
+    FilterableTexture,
+    FilteringSampler,
+}
+
+impl ProvidedBinding {
+    fn at(group: u32, binding: u32) -> Option<Self> {
+        match (group, binding) {
+            (0, 0) => Some(Self::Uniform {
+                size: std::mem::size_of::<glam::Mat4>() as u64,
+                vertex: true,
+                fragment: false,
+            }),
+            (1, 0) => Some(Self::Uniform {
+                size: LIGHT_UNIFORM_SIZE,
+                vertex: true,
+                fragment: true,
+            }),
+            (1, 1) => Some(Self::DepthTexture),
+            (1, 2) => Some(Self::ComparisonSampler),
+            (2, 0) => Some(Self::FilterableTexture),
+            (2, 1) => Some(Self::FilteringSampler),
+            _ => None,
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            Self::Uniform { size, .. } => format!("a uniform of at most {} bytes", size),
+            Self::DepthTexture => "a texture_depth_2d".to_string(),
+            Self::ComparisonSampler => "a sampler_comparison".to_string(),
+            Self::FilterableTexture => "a texture_2d<f32>".to_string(),
+            Self::FilteringSampler => "a sampler".to_string(),
+        }
+    }
+
+    fn matches(&self, module: &naga::Module, global: &naga::GlobalVariable) -> bool {
+        use naga::{ImageClass, ImageDimension, TypeInner};
+        let inner = &module.types[global.ty].inner;
+        match self {
+            Self::Uniform { size, .. } => {
+                global.space == naga::AddressSpace::Uniform && inner.size(module.to_ctx()) as u64 <= *size
+            }
+            Self::DepthTexture => matches!(
+                inner,
+                TypeInner::Image { dim: ImageDimension::D2, arrayed: false, class: ImageClass::Depth { multi: false } }
+            ),
+            Self::ComparisonSampler => matches!(inner, TypeInner::Sampler { comparison: true }),
+            Self::FilterableTexture => matches!(
+                inner,
+                TypeInner::Image {
+                    dim: ImageDimension::D2,
+                    arrayed: false,
+                    class: ImageClass::Sampled { kind: naga::ScalarKind::Float, multi: false },
+                }
+            ),
+            Self::FilteringSampler => matches!(inner, TypeInner::Sampler { comparison: false }),
+        }
+    }
+
+    fn visible_to(&self, stage: naga::ShaderStage) -> bool {
+        match self {
+            Self::Uniform { vertex, fragment, .. } => match stage {
+                naga::ShaderStage::Vertex => *vertex,
+                _ => *fragment,
+            },
+            _ => stage == naga::ShaderStage::Fragment,
+        }
+    }
+}
+
+/// Check the entry points' inputs, bindings and outputs against what the renderer provides
+fn check_contract(module: &naga::Module, info: &naga::valid::ModuleInfo) -> Result<(), String> {
+    for (index, entry) in module.entry_points.iter().enumerate() {
+        let is_pipeline_entry = matches!(
+            (entry.name.as_str(), entry.stage),
+            (VERTEX_ENTRY_POINT, naga::ShaderStage::Vertex) | (FRAGMENT_ENTRY_POINT, naga::ShaderStage::Fragment)
+        );
+        if !is_pipeline_entry {
+            continue;
+        }
+
+        // Only the resources an entry point uses have to be bound
+        let uses = info.get_entry_point(index);
+        for (handle, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+            if uses[handle].is_empty() {
+                continue;
+            }
+            let described = format!(
+                "'{}' at @group({}) @binding({})",
+                global.name.as_deref().unwrap_or("_"),
+                binding.group,
+                binding.binding
+            );
+            let Some(provided) = ProvidedBinding::at(binding.group, binding.binding) else {
+                return Err(format!("{} isn't bound by the renderer", described));
+            };
+            if !provided.matches(module, global) {
+                return Err(format!("{} must be {}", described, provided.description()));
+            }
+            if !provided.visible_to(entry.stage) {
+                return Err(format!("{} isn't visible to '{}'", described, entry.name));
+            }
+        }
+
+        match entry.stage {
+            naga::ShaderStage::Vertex => {
+                for argument in &entry.function.arguments {
+                    for (location, ty) in locations(module, argument.ty, argument.binding.as_ref()) {
+                        let Some(expected) = vertex_input(location) else {
+                            return Err(format!("vertex input @location({}) isn't provided", location));
+                        };
+                        if numeric_type(&module.types[ty].inner) != Some(expected) {
+                            return Err(format!(
+                                "vertex input @location({}) must be {}",
+                                location,
+                                numeric_type_name(expected)
+                            ));
+                        }
+                    }
+                }
+            }
+            _ => {
+                let outputs = entry.function.result.as_ref().map_or(Vec::new(), |result| {
+                    locations(module, result.ty, result.binding.as_ref())
+                });
+                let writes_one_color = match outputs.as_slice() {
+                    [(0, ty)] => matches!(numeric_type(&module.types[*ty].inner), Some((naga::ScalarKind::Float, _))),
+                    _ => false,
+                };
+                if !writes_one_color {
+                    return Err(format!("'{}' must write a single float color to @location(0)", entry.name));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Compile a WGSL source that has already passed [`validate_wgsl`]
+pub(crate) fn create_module(device: &wgpu::Device, name: &str, source: &str) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(name),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+/// Shader modules by name, starting with the built-in shader
+pub struct ShaderRegistry {
+    modules: HashMap<ShaderId, (String, wgpu::ShaderModule)>,
+    ids: HashMap<String, ShaderId>,
+    next_id: u32,
+}
+
+impl ShaderRegistry {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let mut registry = Self {
+            modules: HashMap::new(),
+            ids: HashMap::new(),
+            next_id: 0,
+        };
+        registry
+            .register(device, BASIC_SHADER_NAME, include_str!("../shaders/basic.wgsl"))
+            .expect("the built-in shader is valid");
+        for shader in DebugShader::ALL {
+            registry
+                .register(device, shader.name(), &shader.source())
+                .expect("the debug shaders are valid");
+        }
+        registry
+    }
+
+    /// Compile a shader under a name, replacing any shader already registered with it
+    ///
+    /// Replacing a shader keeps its id. On error the registry is left unchanged.
+    pub fn register(&mut self, device: &wgpu::Device, name: &str, source: &str) -> Result<ShaderId, ShaderError> {
+        validate_wgsl(name, source)?;
+        Ok(self.insert(name, create_module(device, name, source)))
+    }
+
+    /// Add an already compiled module under a name, replacing any shader already registered with it
+    pub(crate) fn insert(&mut self, name: &str, module: wgpu::ShaderModule) -> ShaderId {
+        let id = *self.ids.entry(name.to_string()).or_insert_with(|| {
+            let id = ShaderId(self.next_id);
+            self.next_id += 1;
+            id
+        });
+        self.modules.insert(id, (name.to_string(), module));
+        id
+    }
+
+    /// Look up a shader by name
+    pub fn id(&self, name: &str) -> Option<ShaderId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Name a shader was registered under
+    pub fn name(&self, id: ShaderId) -> Option<&str> {
+        self.modules.get(&id).map(|(name, _)| name.as_str())
+    }
+
+    /// Compiled module for a shader, falling back to the built-in shader for unknown ids
+    pub fn module(&self, id: ShaderId) -> &wgpu::ShaderModule {
+        let (_, module) = self
+            .modules
+            .get(&id)
+            .or_else(|| self.modules.get(&ShaderId::BASIC))
+            .expect("the built-in shader is always registered");
+        module
+    }
+
+    pub fn contains(&self, id: ShaderId) -> bool {
+        self.modules.contains_key(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_shader_follows_contract() {
+        assert_eq!(validate_wgsl(BASIC_SHADER_NAME, include_str!("../shaders/basic.wgsl")), Ok(()));
+    }
+
+    #[test]
+    fn test_internal_shaders_are_valid() {
+        // Shaders with their own pipelines, which don't have to follow the material shader contract
+        for (name, source) in [
+            ("background", include_str!("../shaders/background.wgsl")),
+            ("lines", include_str!("../shaders/lines.wgsl")),
+            ("pick", include_str!("../shaders/pick.wgsl")),
+            ("post", include_str!("../shaders/post.wgsl")),
+            ("shadow", include_str!("../shaders/shadow.wgsl")),
+            ("sky", include_str!("../shaders/sky.wgsl")),
+        ] {
+            assert_eq!(parse_and_validate(name, source).map(|_| ()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_debug_shaders_follow_contract() {
+        for shader in DebugShader::ALL {
+            assert_eq!(validate_wgsl(shader.name(), &shader.source()), Ok(()));
+        }
+    }
+
+    /// Source with one vertex input, one binding used by each stage, and a fragment output
+    fn contract_source(vertex_input: &str, binding: &str, fragment_use: &str, output: &str) -> String {
+        format!(
+            "{binding}
+            @vertex fn vs_main(@location({vertex_input}) -> @builtin(position) vec4<f32> {{ return view_proj * vec4<f32>(1.0); }}
+            @fragment fn fs_main() -> {output} {{ {fragment_use} }}"
+        )
+    }
+
+    #[test]
+    fn test_contract_accepts_provided_interface() {
+        let source = contract_source(
+            "0) position: vec3<f32>",
+            "@group(0) @binding(0) var<uniform> view_proj: mat4x4<f32>;
+            @group(2) @binding(0) var base_texture: texture_2d<f32>;
+            @group(2) @binding(1) var base_sampler: sampler;",
+            "return textureSample(base_texture, base_sampler, vec2<f32>(0.5));",
+            "@location(0) vec4<f32>",
+        );
+        assert_eq!(validate_wgsl("provided", &source), Ok(()));
+    }
+
+    #[test]
+    fn test_contract_violations_are_rejected() {
+        let view_proj = "@group(0) @binding(0) var<uniform> view_proj: mat4x4<f32>;";
+        let color = "return vec4<f32>(1.0);";
+        let output = "@location(0) vec4<f32>";
+        let cases = [
+            // A vertex input at a location nothing feeds
+            contract_source("4) extra: vec4<f32>", view_proj, color, output),
+            // A provided vertex input with the wrong type
+            contract_source("0) position: vec4<f32>", view_proj, color, output),
+            contract_source("15) pick_id: f32", view_proj, color, output),
+            // A group the pipeline layout doesn't have
+            contract_source(
+                "0) position: vec3<f32>",
+                &format!("{view_proj} @group(3) @binding(0) var<uniform> extra: vec4<f32>;"),
+                "return extra;",
+                output,
+            ),
+            // A provided binding with the wrong type
+            contract_source(
+                "0) position: vec3<f32>",
+                &format!("{view_proj} @group(2) @binding(0) var<uniform> base_texture: vec4<f32>;"),
+                "return base_texture;",
+                output,
+            ),
+            // The camera block is larger than a matrix
+            contract_source(
+                "0) position: vec3<f32>",
+                "struct Camera { view_proj: mat4x4<f32>, extra: vec4<f32> }
+                @group(0) @binding(0) var<uniform> camera: Camera;
+                const view_proj = mat4x4<f32>();",
+                "return camera.extra;",
+                output,
+            ),
+            // The camera isn't visible to the fragment stage
+            contract_source("0) position: vec3<f32>", view_proj, "return view_proj[0];", output),
+            // No color, a second color target, or a color at the wrong location
+            contract_source("0) position: vec3<f32>", view_proj, "return 1.0;", "@builtin(frag_depth) f32"),
+            contract_source(
+                "0) position: vec3<f32>",
+                &format!("{view_proj} struct Colors {{ @location(0) a: vec4<f32>, @location(1) b: vec4<f32> }}"),
+                "return Colors(vec4<f32>(1.0), vec4<f32>(1.0));",
+                "Colors",
+            ),
+            contract_source("0) position: vec3<f32>", view_proj, color, "@location(1) vec4<f32>"),
+            contract_source("0) position: vec3<f32>", view_proj, "return vec4<u32>(1u);", "@location(0) vec4<u32>"),
+        ];
+
+        for source in cases {
+            assert!(
+                matches!(validate_wgsl("broken", &source), Err(ShaderError::Interface { .. })),
+                "accepted:\n{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_shaders_are_rejected() {
+        assert!(matches!(
+            validate_wgsl("broken", "fn vs_main( {"),
+            Err(ShaderError::Parse { .. })
+        ));
+
+        let vertex_only = "@vertex fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }";
+        assert_eq!(
+            validate_wgsl("vertex_only", vertex_only),
+            Err(ShaderError::MissingEntryPoint {
+                name: "vertex_only".to_string(),
+                entry_point: FRAGMENT_ENTRY_POINT,
+            })
+        );
+    }
+}
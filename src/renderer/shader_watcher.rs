@@ -0,0 +1,167 @@
+//! Shader hot-reload for development builds
+//!
+//! Watches a directory of `.wgsl` material shaders by polling their modification
+//! times. Each file is registered as the shader named after its file stem, so
+//! `pulse.wgsl` replaces the `pulse` shader and `basic.wgsl` replaces the
+//! built-in shader. The crate's other built-in shaders aren't material shaders
+//! and are skipped, so `src/shaders` itself can be watched. Only available in
+//! debug builds on native targets.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use web_time::Instant;
+
+use crate::renderer::pipeline::RenderPipeline;
+
+/// How often the directory is checked by default
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Built-in shaders in `src/shaders` that are compiled into their own renderers rather than used by materials
+const NON_MATERIAL_SHADERS: &[&str] = &["background", "debug", "lines", "pick", "post", "shadow", "sky"];
+
+/// A shader file whose contents changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedShader {
+    /// Shader name, taken from the file stem
+    pub name: String,
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// Polls a directory for new or modified WGSL files
+pub struct ShaderWatcher {
+    directory: PathBuf,
+    /// Modification time and length of each file as of its last successful read
+    seen: HashMap<PathBuf, (SystemTime, u64)>,
+    poll_interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl ShaderWatcher {
+    /// Watch a directory; fails if it doesn't exist or can't be read
+    pub fn new(directory: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let directory = directory.into();
+        std::fs::read_dir(&directory)?;
+
+        Ok(Self {
+            directory,
+            seen: HashMap::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            last_poll: None,
+        })
+    }
+
+    /// Check the directory at most this often
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Shaders added or modified since the last poll; every shader on the first poll
+    ///
+    /// Files named after the built-in non-material shaders, such as `sky.wgsl`, are never reported.
+    ///
+    /// Returns nothing when called again before the poll interval has passed.
+    /// Files that can't be read yet, such as ones still being written, are retried
+    /// on the next poll.
+    pub fn poll(&mut self) -> Vec<ChangedShader> {
+        if self.last_poll.is_some_and(|last_poll| last_poll.elapsed() < self.poll_interval) {
+            return Vec::new();
+        }
+        self.last_poll = Some(Instant::now());
+
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(error) => {
+                log::warn!("Can't read shader directory {}: {}", self.directory.display(), error);
+                return Vec::new();
+            }
+        };
+
+        let mut changed = Vec::new();
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.extension().and_then(|extension| extension.to_str()) != Some("wgsl") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string) else {
+                continue;
+            };
+            if NON_MATERIAL_SHADERS.contains(&name.as_str()) {
+                continue;
+            }
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let stamp = (metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len());
+            if self.seen.get(&path) == Some(&stamp) {
+                continue;
+            }
+
+            match std::fs::read_to_string(&path) {
+                Ok(source) => {
+                    self.seen.insert(path.clone(), stamp);
+                    changed.push(ChangedShader { name, path, source });
+                }
+                Err(error) => log::warn!("Can't read shader {}: {}", path.display(), error),
+            }
+        }
+
+        changed.sort_by(|a, b| a.name.cmp(&b.name));
+        changed
+    }
+
+    /// Register every changed shader with the pipeline's cache, rebuilding its pipelines
+    ///
+    /// Shaders that fail to compile or to build their pipelines are logged, and the
+    /// previous version of the shader and its pipelines stay in use.
+    /// Returns the number of shaders reloaded.
+    pub fn reload_changed(&mut self, device: &wgpu::Device, pipeline: &mut RenderPipeline) -> usize {
+        let mut reloaded = 0;
+        for shader in self.poll() {
+            match pollster::block_on(pipeline.register_shader(device, &shader.name, &shader.source)) {
+                Ok(_) => {
+                    log::info!("Reloaded shader '{}' from {}", shader.name, shader.path.display());
+                    reloaded += 1;
+                }
+                Err(error) => log::error!("{}\nKeeping the previous version of '{}'", error, shader.name),
+            }
+        }
+        reloaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_reports_new_and_modified_shaders() {
+        let directory = std::env::temp_dir().join(format!("map_shader_watcher_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("pulse.wgsl"), "// first").unwrap();
+        std::fs::write(directory.join("notes.txt"), "not a shader").unwrap();
+        std::fs::write(directory.join("sky.wgsl"), "// built-in sky").unwrap();
+
+        let mut watcher = ShaderWatcher::new(&directory).unwrap().with_poll_interval(Duration::ZERO);
+        let changed = watcher.poll();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].name, "pulse");
+        assert_eq!(changed[0].source, "// first");
+        assert!(watcher.poll().is_empty());
+
+        // A different length is detected even when the modification time has coarse resolution
+        std::fs::write(directory.join("pulse.wgsl"), "// second version").unwrap();
+        let changed = watcher.poll();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].source, "// second version");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(ShaderWatcher::new(&directory).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Directional light shadow mapping
+//!
+//! Shadow casters are drawn into a depth-only shadow map from the directional
+//! light's point of view, through an orthographic projection fitted around the
+//! bounds of the whole scene. The basic shader then compares each surface's
+//! depth as seen by the light against the map, filtering several comparisons
+//! (PCF) to soften the shadow's edges.
+//!
+//! Casters are drawn whether or not the camera can see them, since an object
+//! outside the view can still shadow one inside it.
+
+use glam::{Mat4, Vec3};
+
+use crate::renderable::Vertex;
+use crate::renderer::instance_buffer::InstanceData;
+use crate::renderer::pipeline_cache::DepthState;
+
+/// Smallest scene radius the light's projection is fitted to, so a single flat object still gets a usable volume
+const MIN_SCENE_RADIUS: f32 = 0.01;
+
+/// View-projection looking along `direction` that encloses the box from `min` to `max`
+///
+/// The projection is fitted around the box's bounding sphere, so every point of
+/// the box lands inside the light's clip volume however the light is turned.
+pub fn light_view_projection(direction: Vec3, min: Vec3, max: Vec3) -> Mat4 {
+    let center = (min + max) * 0.5;
+    // Slightly padded so surfaces on the sphere's edge aren't clipped by rounding
+    let radius = ((max - min).length() * 0.5).max(MIN_SCENE_RADIUS) * 1.01;
+    let direction = direction.try_normalize().unwrap_or(Vec3::NEG_Y);
+    // Any up vector works as long as it isn't parallel to the light
+    let up = if direction.cross(Vec3::Y).length_squared() < 1e-6 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    };
+
+    // The camera sits outside the sphere, which then spans depths radius to 3 * radius
+    let view = Mat4::look_at_rh(center - direction * radius * 2.0, center, up);
+    let projection = Mat4::orthographic_rh(-radius, radius, -radius, radius, radius, radius * 3.0);
+    projection * view
+}
+
+/// Depth texture the casters are drawn into
+struct ShadowMap {
+    resolution: u32,
+    view: wgpu::TextureView,
+}
+
+/// Draws shadow casters into the directional light's shadow map
+pub struct ShadowRenderer {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    map: Option<ShadowMap>,
+}
+
+impl ShadowRenderer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/shadow.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            size: std::mem::size_of::<Mat4>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[Vertex::desc(), InstanceData::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: None,
+            // Both sides are drawn, so flat casters like quads and triangles shadow from either side
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                // Push depths back on sloped surfaces, where a texel covers a range of depths
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+                ..DepthState::default().to_wgpu()
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            map: None,
+        }
+    }
+
+    /// Make sure the shadow map has the given resolution, returning true if it was (re)created
+    ///
+    /// Bind groups sampling the previous map have to be rebuilt with [`Self::map_view`].
+    pub fn ensure_map(&mut self, device: &wgpu::Device, resolution: u32) -> bool {
+        let resolution = resolution.clamp(1, device.limits().max_texture_dimension_2d);
+        if self.map.as_ref().is_some_and(|map| map.resolution == resolution) {
+            return false;
+        }
+
+        log::debug!("Creating {}x{} shadow map", resolution, resolution);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DepthState::default().format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        self.map = Some(ShadowMap {
+            resolution,
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        });
+        true
+    }
+
+    /// View of the current shadow map, once one has been created
+    pub fn map_view(&self) -> Option<&wgpu::TextureView> {
+        self.map.as_ref().map(|map| &map.view)
+    }
+
+    /// Upload the light's view-projection for the next shadow pass
+    pub fn prepare(&self, queue: &wgpu::Queue, light_view_proj: Mat4) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&light_view_proj.to_cols_array()));
+    }
+
+    /// Begin a pass clearing the shadow map, with the shadow pipeline and light bound
+    ///
+    /// Returns `None` before [`Self::ensure_map`] has created a map. Casters are
+    /// drawn with their vertex buffer in slot 0 and instances in slot 1.
+    pub fn begin_pass<'e>(&self, encoder: &'e mut wgpu::CommandEncoder) -> Option<wgpu::RenderPass<'e>> {
+        let map = self.map.as_ref()?;
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Render Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &map.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        Some(render_pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_projection_encloses_scene() {
+        let (min, max) = (Vec3::new(-4.0, -1.0, -10.0), Vec3::new(6.0, 3.0, 0.0));
+        let corners = (0..8).map(|index| {
+            Vec3::new(
+                if index & 1 == 0 { min.x } else { max.x },
+                if index & 2 == 0 { min.y } else { max.y },
+                if index & 4 == 0 { min.z } else { max.z },
+            )
+        });
+
+        for direction in [Vec3::new(-0.3, -0.5, -1.0), Vec3::NEG_Y, Vec3::Y, Vec3::X] {
+            let view_proj = light_view_projection(direction, min, max);
+            for corner in corners.clone() {
+                let clip = view_proj.project_point3(corner);
+                assert!(clip.x.abs() <= 1.0 && clip.y.abs() <= 1.0, "{:?} outside for {:?}", corner, direction);
+                assert!((0.0..=1.0).contains(&clip.z), "{:?} clipped for {:?}", corner, direction);
+            }
+
+            // Points further along the light's direction are deeper in the map
+            let center = (min + max) * 0.5;
+            let near = view_proj.project_point3(center - direction.normalize());
+            let far = view_proj.project_point3(center + direction.normalize());
+            assert!(near.z < far.z);
+        }
+    }
+}
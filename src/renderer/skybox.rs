@@ -0,0 +1,387 @@
+//! Skies and environment backgrounds
+//!
+//! Procedural skies, cubemaps and equirectangular panoramas are drawn as a
+//! fullscreen triangle on the far plane, with each pixel looking up the sky
+//! along its view direction. The sky is drawn at the start of the frame's
+//! render pass and tests against the depth buffer without writing to it, so
+//! objects always appear in front of it.
+//!
+//! Orthographic cameras have no field of view of their own, so the sky is
+//! looked up as if the camera had its perspective field of view. Either way
+//! it turns with the camera but never moves when the camera does.
+
+use std::collections::HashMap;
+
+use crate::renderer::background::color_to_array;
+use crate::renderer::camera::{Camera, FIELD_OF_VIEW_DEGREES};
+use crate::renderer::config::BackgroundMode;
+use crate::renderer::image::Image;
+use crate::renderer::pipeline_cache::DepthState;
+use crate::renderer::texture::{Texture, TextureError};
+
+/// Handle to a cubemap or panorama registered with a [`SkyboxRenderer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EnvironmentId(u32);
+
+/// Which fragment shader and texture binding a sky uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SkyKind {
+    Procedural,
+    Cubemap,
+    Panorama,
+}
+
+impl SkyKind {
+    fn entry_point(self) -> &'static str {
+        match self {
+            SkyKind::Procedural => "fs_sky",
+            SkyKind::Cubemap => "fs_cubemap",
+            SkyKind::Panorama => "fs_panorama",
+        }
+    }
+}
+
+/// Camera basis and sky colors as laid out in the shader's uniform block
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyUniform {
+    right: [f32; 4],
+    up: [f32; 4],
+    forward: [f32; 4],
+    zenith: [f32; 4],
+    horizon: [f32; 4],
+    ground: [f32; 4],
+}
+
+impl SkyUniform {
+    /// Basis scaled so normalized device coordinates map onto the field of view
+    fn new(camera: &Camera, colors: [wgpu::Color; 3]) -> Self {
+        let view = camera.view_matrix;
+        let tan_half_fov = (FIELD_OF_VIEW_DEGREES.to_radians() * 0.5).tan();
+        let right = view.row(0).truncate() * tan_half_fov * camera.aspect_ratio;
+        let up = view.row(1).truncate() * tan_half_fov;
+        let forward = -view.row(2).truncate();
+        let [zenith, horizon, ground] = colors.map(color_to_array);
+
+        Self {
+            right: right.extend(0.0).to_array(),
+            up: up.extend(0.0).to_array(),
+            forward: forward.extend(0.0).to_array(),
+            zenith,
+            horizon,
+            ground,
+        }
+    }
+}
+
+/// A registered environment texture and the bind group that samples it
+struct Environment {
+    kind: SkyKind,
+    #[allow(dead_code)]
+    texture: Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Draws skies and owns the environment textures they sample
+pub struct SkyboxRenderer {
+    shader: wgpu::ShaderModule,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    cubemap_layout: wgpu::BindGroupLayout,
+    panorama_layout: wgpu::BindGroupLayout,
+    pipeline_layouts: HashMap<SkyKind, wgpu::PipelineLayout>,
+    sampler: wgpu::Sampler,
+    environments: HashMap<EnvironmentId, Environment>,
+    next_id: u32,
+    pipelines: HashMap<(SkyKind, wgpu::TextureFormat, u32), wgpu::RenderPipeline>,
+    /// Sky set up by the last successful `prepare`
+    prepared: Option<(SkyKind, Option<EnvironmentId>)>,
+}
+
+impl SkyboxRenderer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sky Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/sky.wgsl").into()),
+        });
+
+        let uniform_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Uniform Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let cubemap_layout = Self::create_environment_layout(device, 0, wgpu::TextureViewDimension::Cube);
+        let panorama_layout = Self::create_environment_layout(device, 1, wgpu::TextureViewDimension::D2);
+
+        let pipeline_layouts = [
+            (SkyKind::Procedural, vec![&uniform_layout]),
+            (SkyKind::Cubemap, vec![&uniform_layout, &cubemap_layout]),
+            (SkyKind::Panorama, vec![&uniform_layout, &panorama_layout]),
+        ]
+        .into_iter()
+        .map(|(kind, bind_group_layouts)| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Sky Pipeline Layout"),
+                bind_group_layouts: &bind_group_layouts,
+                push_constant_ranges: &[],
+            });
+            (kind, layout)
+        })
+        .collect();
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sky Uniform Buffer"),
+            size: std::mem::size_of::<SkyUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sky Uniform Bind Group"),
+            layout: &uniform_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        // Panoramas wrap around horizontally but not over the poles
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Sky Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            shader,
+            uniform_buffer,
+            uniform_bind_group,
+            cubemap_layout,
+            panorama_layout,
+            pipeline_layouts,
+            sampler,
+            environments: HashMap::new(),
+            next_id: 0,
+            pipelines: HashMap::new(),
+            prepared: None,
+        }
+    }
+
+    /// Layout with the environment texture at `texture_binding` and the sampler at binding 2
+    fn create_environment_layout(
+        device: &wgpu::Device,
+        texture_binding: u32,
+        view_dimension: wgpu::TextureViewDimension,
+    ) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Environment Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: texture_binding,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    fn add_environment(&mut self, device: &wgpu::Device, kind: SkyKind, texture: Texture) -> EnvironmentId {
+        let (layout, texture_binding) = match kind {
+            SkyKind::Cubemap => (&self.cubemap_layout, 0),
+            _ => (&self.panorama_layout, 1),
+        };
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sky Environment Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: texture_binding,
+                    resource: wgpu::BindingResource::TextureView(texture.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        let id = EnvironmentId(self.next_id);
+        self.next_id += 1;
+        self.environments.insert(id, Environment { kind, texture, bind_group });
+        id
+    }
+
+    /// Register six square faces as a cubemap, in the order +X, -X, +Y, -Y, +Z, -Z
+    pub fn add_cubemap(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: &[Image; 6],
+    ) -> Result<EnvironmentId, TextureError> {
+        let texture = Texture::cubemap_from_images(device, queue, faces, Some("Sky Cubemap"))?;
+        Ok(self.add_environment(device, SkyKind::Cubemap, texture))
+    }
+
+    /// Register an equirectangular panorama, longitude across its width and the zenith at the top
+    pub fn add_panorama(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &Image,
+    ) -> Result<EnvironmentId, TextureError> {
+        let texture = Texture::from_image(device, queue, image, Some("Sky Panorama"))?;
+        Ok(self.add_environment(device, SkyKind::Panorama, texture))
+    }
+
+    /// Unregister an environment; backgrounds still using it fall back to their clear color
+    pub fn remove(&mut self, id: EnvironmentId) -> bool {
+        self.environments.remove(&id).is_some()
+    }
+
+    pub fn contains(&self, id: EnvironmentId) -> bool {
+        self.environments.contains_key(&id)
+    }
+
+    /// Upload the camera's view of the sky and build its pipeline if needed
+    /// Returns false when the background isn't a sky, or names an unknown environment
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        background: &BackgroundMode,
+        camera: &Camera,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> bool {
+        let (kind, environment, colors) = match *background {
+            BackgroundMode::Sky { zenith, horizon, ground } => (SkyKind::Procedural, None, [zenith, horizon, ground]),
+            BackgroundMode::Environment(id) => match self.environments.get(&id) {
+                Some(environment) => (environment.kind, Some(id), [wgpu::Color::BLACK; 3]),
+                None => {
+                    log::warn!("Background uses unknown environment {:?}", id);
+                    self.prepared = None;
+                    return false;
+                }
+            },
+            _ => {
+                self.prepared = None;
+                return false;
+            }
+        };
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&SkyUniform::new(camera, colors)));
+
+        let Self { shader, pipeline_layouts, pipelines, .. } = self;
+        pipelines.entry((kind, format, sample_count)).or_insert_with(|| {
+            Self::create_pipeline(device, shader, &pipeline_layouts[&kind], kind, format, sample_count)
+        });
+        self.prepared = Some((kind, environment));
+        true
+    }
+
+    /// Draw the prepared sky; it tests depth on the far plane and never writes it
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass, format: wgpu::TextureFormat, sample_count: u32) {
+        let Some((kind, environment)) = self.prepared else {
+            return;
+        };
+        let Some(pipeline) = self.pipelines.get(&(kind, format, sample_count)) else {
+            return;
+        };
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        if let Some(environment) = environment.and_then(|id| self.environments.get(&id)) {
+            render_pass.set_bind_group(1, &environment.bind_group, &[]);
+        }
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        kind: SkyKind,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sky Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(kind.entry_point()),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                ..DepthState::default().to_wgpu()
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    #[test]
+    fn test_sky_basis_follows_camera() {
+        let mut camera = Camera::new(2.0);
+        camera.set_position(Vec3::new(0.0, 0.0, 5.0));
+        let uniform = SkyUniform::new(&camera, [wgpu::Color::WHITE; 3]);
+
+        // Looking down -Z with +X to the right, at any camera position
+        let tan_half_fov = (FIELD_OF_VIEW_DEGREES.to_radians() * 0.5).tan();
+        assert!(Vec3::from_slice(&uniform.forward).abs_diff_eq(Vec3::NEG_Z, 1e-5));
+        assert!(Vec3::from_slice(&uniform.right).abs_diff_eq(Vec3::X * tan_half_fov * 2.0, 1e-5));
+        assert!(Vec3::from_slice(&uniform.up).abs_diff_eq(Vec3::Y * tan_half_fov, 1e-5));
+
+        camera.set_projection_mode(crate::renderer::camera::ProjectionMode::Orthographic);
+        let orthographic = SkyUniform::new(&camera, [wgpu::Color::WHITE; 3]);
+        assert_eq!(orthographic.forward, uniform.forward);
+    }
+}
@@ -0,0 +1,409 @@
+//! Textures sampled by the basic shader
+//!
+//! Textures are uploaded from RGBA8 pixel data and registered with a
+//! [`TextureStore`], which hands out [`TextureId`]s for materials to reference.
+//! Objects without a texture sample a 1x1 white texture, so every draw binds
+//! the same layout and untextured objects look the same as before.
+
+use std::collections::HashMap;
+
+use crate::renderer::image::{Image, ImageFormat};
+
+/// Handle to a texture registered with a [`TextureStore`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureId(u32);
+
+/// Errors produced when pixel data can't be turned into a texture
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureError {
+    /// Width or height is zero
+    EmptyDimensions { width: u32, height: u32 },
+    /// Width or height exceeds the device's texture size limit
+    TooLarge { width: u32, height: u32, max: u32 },
+    /// The pixel data doesn't hold exactly `width * height` RGBA8 pixels
+    DataSizeMismatch { expected: usize, actual: usize },
+    /// A cubemap face isn't square or doesn't match the size of the first face
+    CubemapFaceMismatch { face: usize, width: u32, height: u32, size: u32 },
+}
+
+impl std::fmt::Display for TextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureError::EmptyDimensions { width, height } => {
+                write!(f, "Texture dimensions {}x{} must both be non-zero", width, height)
+            }
+            TextureError::TooLarge { width, height, max } => write!(
+                f,
+                "Texture dimensions {}x{} exceed the device limit of {}",
+                width, height, max
+            ),
+            TextureError::DataSizeMismatch { expected, actual } => write!(
+                f,
+                "Texture data is {} bytes but the dimensions need {} bytes of RGBA8 pixels",
+                actual, expected
+            ),
+            TextureError::CubemapFaceMismatch { face, width, height, size } => write!(
+                f,
+                "Cubemap face {} is {}x{} but every face must be {}x{}",
+                face, width, height, size, size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+/// A 2D texture on the GPU
+pub struct Texture {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Texture {
+    /// Upload tightly packed RGBA8 pixels, top row first
+    ///
+    /// `srgb` marks the color channels as sRGB-encoded, as in most imagery;
+    /// pass false for data such as height or normal maps.
+    pub fn from_rgba8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        srgb: bool,
+        label: Option<&str>,
+    ) -> Result<Self, TextureError> {
+        validate_rgba8(width, height, data.len(), device.limits().max_texture_dimension_2d)?;
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: if srgb {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            },
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(Self { texture, view })
+    }
+
+    /// Upload an image, keeping its color space
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &Image,
+        label: Option<&str>,
+    ) -> Result<Self, TextureError> {
+        let srgb = image.format() == ImageFormat::Rgba8Srgb;
+        Self::from_rgba8(device, queue, image.width(), image.height(), image.data(), srgb, label)
+    }
+
+    /// Upload six square faces as a cubemap, in the order +X, -X, +Y, -Y, +Z, -Z
+    ///
+    /// The first face's color space is used for all of them.
+    pub fn cubemap_from_images(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: &[Image; 6],
+        label: Option<&str>,
+    ) -> Result<Self, TextureError> {
+        let size = validate_cubemap_faces(&faces.each_ref().map(|face| (face.width(), face.height())))?;
+        for face in faces {
+            validate_rgba8(size, size, face.data().len(), device.limits().max_texture_dimension_2d)?;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: if faces[0].format() == ImageFormat::Rgba8Srgb {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            },
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, face) in faces.iter().enumerate() {
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                face.data(),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * size),
+                    rows_per_image: Some(size),
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        Ok(Self { texture, view })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
+
+/// Check RGBA8 dimensions and data length before creating a texture
+fn validate_rgba8(width: u32, height: u32, data_len: usize, max_dimension: u32) -> Result<(), TextureError> {
+    if width == 0 || height == 0 {
+        return Err(TextureError::EmptyDimensions { width, height });
+    }
+    if width > max_dimension || height > max_dimension {
+        return Err(TextureError::TooLarge {
+            width,
+            height,
+            max: max_dimension,
+        });
+    }
+
+    let expected = width as usize * height as usize * 4;
+    if data_len != expected {
+        return Err(TextureError::DataSizeMismatch {
+            expected,
+            actual: data_len,
+        });
+    }
+    Ok(())
+}
+
+/// Registered textures and their bind groups, bound at group 2 of the basic shader
+pub struct TextureStore {
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    /// White texture bound for objects without a texture
+    default_bind_group: wgpu::BindGroup,
+    textures: HashMap<TextureId, (Texture, wgpu::BindGroup)>,
+    next_id: u32,
+}
+
+impl TextureStore {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        // Repeat so UVs outside 0..1 tile the texture
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Texture Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let white = Texture::from_rgba8(device, queue, 1, 1, &[255; 4], true, Some("Default White Texture"))
+            .expect("a 1x1 texture is always valid");
+        let default_bind_group = Self::create_bind_group(device, &bind_group_layout, &sampler, &white);
+
+        Self {
+            bind_group_layout,
+            sampler,
+            default_bind_group,
+            textures: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        texture: &Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Texture Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(texture.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Register a texture and return the handle materials use to reference it
+    pub fn add(&mut self, device: &wgpu::Device, texture: Texture) -> TextureId {
+        let id = TextureId(self.next_id);
+        self.next_id += 1;
+
+        let bind_group = Self::create_bind_group(device, &self.bind_group_layout, &self.sampler, &texture);
+        self.textures.insert(id, (texture, bind_group));
+        id
+    }
+
+    /// Upload RGBA8 pixels and register them as a texture
+    pub fn add_rgba8(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<TextureId, TextureError> {
+        let texture = Texture::from_rgba8(device, queue, width, height, data, true, Some("Texture"))?;
+        Ok(self.add(device, texture))
+    }
+
+    /// Unregister a texture; objects still referencing it fall back to white
+    pub fn remove(&mut self, id: TextureId) -> Option<Texture> {
+        self.textures.remove(&id).map(|(texture, _)| texture)
+    }
+
+    pub fn get(&self, id: TextureId) -> Option<&Texture> {
+        self.textures.get(&id).map(|(texture, _)| texture)
+    }
+
+    /// Bind group for a texture, or the white texture when there is none
+    pub fn bind_group(&self, id: Option<TextureId>) -> &wgpu::BindGroup {
+        id.and_then(|id| self.textures.get(&id))
+            .map(|(_, bind_group)| bind_group)
+            .unwrap_or(&self.default_bind_group)
+    }
+
+    /// Get the bind group layout for pipeline creation
+    pub fn get_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    /// Number of registered textures
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+}
+
+/// Check that cubemap faces are square and all the same size, returning that size
+fn validate_cubemap_faces(sizes: &[(u32, u32); 6]) -> Result<u32, TextureError> {
+    let size = sizes[0].0;
+    for (face, &(width, height)) in sizes.iter().enumerate() {
+        if width != size || height != size {
+            return Err(TextureError::CubemapFaceMismatch { face, width, height, size });
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rgba8() {
+        assert_eq!(validate_rgba8(2, 3, 24, 8192), Ok(()));
+        assert_eq!(
+            validate_rgba8(0, 3, 0, 8192),
+            Err(TextureError::EmptyDimensions { width: 0, height: 3 })
+        );
+        assert_eq!(
+            validate_rgba8(2, 3, 18, 8192),
+            Err(TextureError::DataSizeMismatch { expected: 24, actual: 18 })
+        );
+        assert_eq!(
+            validate_rgba8(16384, 1, 65536, 8192),
+            Err(TextureError::TooLarge { width: 16384, height: 1, max: 8192 })
+        );
+    }
+
+    #[test]
+    fn test_validate_cubemap_faces() {
+        assert_eq!(validate_cubemap_faces(&[(16, 16); 6]), Ok(16));
+
+        let mut sizes = [(16, 16); 6];
+        sizes[3] = (16, 8);
+        assert_eq!(
+            validate_cubemap_faces(&sizes),
+            Err(TextureError::CubemapFaceMismatch { face: 3, width: 16, height: 8, size: 16 })
+        );
+    }
+}
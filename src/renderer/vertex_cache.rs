@@ -3,23 +3,114 @@
 //! This module provides caching for vertex buffers to avoid recreating
 //! the same geometry data every frame.
 
-use crate::renderable::VertexProvider;
+use crate::renderable::{Indices, Vertex, VertexProvider};
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 use web_time::Instant;
 use wgpu::util::DeviceExt;
 
 /// Hash key for identifying identical vertex data
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct VertexDataHash(u64);
+pub struct VertexDataHash(u64);
 
 /// Cached vertex buffer with metadata
 pub struct CachedVertexBuffer {
     pub buffer: wgpu::Buffer,
     pub vertex_count: u32,
+    /// Index buffer for indexed geometry
+    pub index_buffer: Option<CachedIndexBuffer>,
+    /// Indexed geometry's vertices repeated in index order, built on demand for the barycentric wireframe
+    pub unindexed_buffer: Option<wgpu::Buffer>,
     last_used: Instant,
 }
 
+/// Cached index buffer belonging to a vertex buffer
+pub struct CachedIndexBuffer {
+    pub buffer: wgpu::Buffer,
+    pub format: wgpu::IndexFormat,
+    pub index_count: u32,
+}
+
+impl CachedVertexBuffer {
+    /// Create the vertex buffer, plus an index buffer if the provider is indexed
+    fn new(provider: &dyn VertexProvider, device: &wgpu::Device, label: &str) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: provider.buffer_contents(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = provider.indices().map(|indices| CachedIndexBuffer {
+            buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Cached Index Buffer"),
+                contents: indices.as_bytes(),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            format: indices.format(),
+            index_count: indices.len() as u32,
+        });
+
+        Self {
+            buffer,
+            vertex_count: provider.vertex_count() as u32,
+            index_buffer,
+            unindexed_buffer: None,
+            last_used: Instant::now(),
+        }
+    }
+
+    /// Build the unindexed copy of indexed geometry if it doesn't exist yet
+    fn ensure_unindexed(&mut self, provider: &dyn VertexProvider, device: &wgpu::Device) {
+        if self.unindexed_buffer.is_some() {
+            return;
+        }
+        if let Some(indices) = provider.indices() {
+            self.unindexed_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Cached Unindexed Vertex Buffer"),
+                contents: bytemuck::cast_slice(&unindexed_vertices(provider.vertices(), indices)),
+                usage: wgpu::BufferUsages::VERTEX,
+            }));
+        }
+    }
+
+    /// Number of vertices processed when drawing this geometry
+    pub fn draw_count(&self) -> u32 {
+        self.index_buffer.as_ref().map_or(self.vertex_count, |index| index.index_count)
+    }
+
+    /// Record a draw of `instances` into a render pass
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass, instances: std::ops::Range<u32>) {
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+        match &self.index_buffer {
+            Some(index) => {
+                render_pass.set_index_buffer(index.buffer.slice(..), index.format);
+                render_pass.draw_indexed(0..index.index_count, 0, instances);
+            }
+            None => render_pass.draw(0..self.vertex_count, instances),
+        }
+    }
+
+    /// Record a draw where every three vertices are their own triangle
+    ///
+    /// Indexed geometry needs its unindexed copy, made by [`VertexBufferCache::prepare_unindexed_buffers`];
+    /// without one it's drawn indexed.
+    pub fn draw_unindexed(&self, render_pass: &mut wgpu::RenderPass, instances: std::ops::Range<u32>) {
+        match &self.unindexed_buffer {
+            Some(buffer) => {
+                render_pass.set_vertex_buffer(0, buffer.slice(..));
+                render_pass.draw(0..self.draw_count(), instances);
+            }
+            None => self.draw(render_pass, instances),
+        }
+    }
+}
+
+/// Vertices in index order, so geometry can be drawn without its index buffer
+pub fn unindexed_vertices(vertices: &[Vertex], indices: &Indices) -> Vec<Vertex> {
+    (0..indices.len())
+        .filter_map(|i| indices.get(i).and_then(|index| vertices.get(index as usize)).copied())
+        .collect()
+}
+
 /// High-performance vertex buffer cache
 pub struct VertexBufferCache {
     cache: HashMap<VertexDataHash, CachedVertexBuffer>,
@@ -55,47 +146,12 @@ impl VertexBufferCache {
         &mut self,
         renderables: &[T],
         device: &wgpu::Device,
-    ) -> Vec<(&wgpu::Buffer, u32)> {
-        let mut result = Vec::new();
-
-        for renderable in renderables.iter() {
-            let contents = renderable.buffer_contents();
-            let hash = Self::hash_vertex_data(contents);
-
-            // Check if we already have this buffer cached
-            if let Some(cached) = self.cache.get_mut(&hash) {
-                // Update the last used time
-                cached.last_used = Instant::now();
-            } else {
-                // Create new buffer
-                let vertex_count = renderable.vertex_count() as u32;
-                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Cached Vertex Buffer"),
-                    contents,
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-
-                let cached_buffer = CachedVertexBuffer {
-                    buffer,
-                    vertex_count,
-                    last_used: Instant::now(),
-                };
-
-                self.cache.insert(hash, cached_buffer);
-            }
-        }
+    ) -> Vec<&CachedVertexBuffer> {
+        let keys: Vec<VertexDataHash> = renderables.iter()
+            .map(|renderable| self.get_or_create(renderable, device, "Cached Vertex Buffer"))
+            .collect();
 
-        // Now collect all the references (this works because we're not mutating anymore)
-        for renderable in renderables.iter() {
-            let contents = renderable.buffer_contents();
-            let hash = Self::hash_vertex_data(contents);
-
-            if let Some(cached) = self.cache.get(&hash) {
-                result.push((&cached.buffer, cached.vertex_count));
-            }
-        }
-
-        result
+        keys.iter().filter_map(|key| self.get(*key)).collect()
     }
 
     /// Process mixed object types by converting to trait objects
@@ -104,55 +160,55 @@ impl VertexBufferCache {
         &mut self,
         objects: &[&dyn VertexProvider],
         device: &wgpu::Device,
-    ) -> Vec<(&wgpu::Buffer, u32)> {
-        let mut result = Vec::new();
-
-        // First pass: create any missing buffers
-        for object in objects.iter() {
-            let contents = object.buffer_contents();
-            let hash = Self::hash_vertex_data(contents);
-
-            // Check if we already have this buffer cached
-            if let Some(cached) = self.cache.get_mut(&hash) {
-                // Update the last used time
-                cached.last_used = Instant::now();
-            } else {
-                // Create new buffer
-                let vertex_count = object.vertex_count() as u32;
-                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Mixed Cached Vertex Buffer"),
-                    contents,
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-
-                let cached_buffer = CachedVertexBuffer {
-                    buffer,
-                    vertex_count,
-                    last_used: Instant::now(),
-                };
-
-                self.cache.insert(hash, cached_buffer);
-            }
-        }
+    ) -> Vec<&CachedVertexBuffer> {
+        let keys = self.prepare_mixed_buffers(objects, device);
 
-        // Second pass: collect all the references
-        for object in objects.iter() {
-            let contents = object.buffer_contents();
-            let hash = Self::hash_vertex_data(contents);
+        keys.iter().filter_map(|key| self.get(*key)).collect()
+    }
 
-            if let Some(cached) = self.cache.get(&hash) {
-                result.push((&cached.buffer, cached.vertex_count));
+    /// Create any missing buffers for mixed objects and return each object's cache key
+    ///
+    /// Objects that share a key share a buffer, so they can be drawn with one instanced draw.
+    pub fn prepare_mixed_buffers(
+        &mut self,
+        objects: &[&dyn VertexProvider],
+        device: &wgpu::Device,
+    ) -> Vec<VertexDataHash> {
+        objects.iter()
+            .map(|object| self.get_or_create(*object, device, "Mixed Cached Vertex Buffer"))
+            .collect()
+    }
+
+    /// Like [`prepare_mixed_buffers`](Self::prepare_mixed_buffers), also making unindexed
+    /// copies of indexed geometry for [`CachedVertexBuffer::draw_unindexed`]
+    pub fn prepare_unindexed_buffers(
+        &mut self,
+        objects: &[&dyn VertexProvider],
+        device: &wgpu::Device,
+    ) -> Vec<VertexDataHash> {
+        let keys = self.prepare_mixed_buffers(objects, device);
+        for (object, key) in objects.iter().zip(&keys) {
+            if let Some(cached) = self.cache.get_mut(key) {
+                cached.ensure_unindexed(*object, device);
             }
         }
+        keys
+    }
+
+    /// Reuse the cached buffers for a provider if present, otherwise create them
+    fn get_or_create(&mut self, provider: &dyn VertexProvider, device: &wgpu::Device, label: &str) -> VertexDataHash {
+        let hash = VertexDataHash(provider.geometry_key());
+
+        self.cache.entry(hash)
+            .and_modify(|cached| cached.last_used = Instant::now())
+            .or_insert_with(|| CachedVertexBuffer::new(provider, device, label));
 
-        result
+        hash
     }
 
-    /// Hash vertex data for cache key
-    fn hash_vertex_data(data: &[u8]) -> VertexDataHash {
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        data.hash(&mut hasher);
-        VertexDataHash(hasher.finish())
+    /// Look up a cached buffer by key
+    pub fn get(&self, key: VertexDataHash) -> Option<&CachedVertexBuffer> {
+        self.cache.get(&key)
     }
 }
 
@@ -161,3 +217,25 @@ impl Default for VertexBufferCache {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unindexed_vertices_follow_index_order() {
+        let vertices: Vec<Vertex> = (0..4)
+            .map(|i| Vertex {
+                position: [i as f32, 0.0, 0.0],
+                color: [1.0; 3],
+                normal: [0.0, 0.0, 1.0],
+                uv: [0.0; 2],
+            })
+            .collect();
+        let indices = Indices::new(vec![0, 1, 2, 2, 3, 0], vertices.len());
+
+        let unindexed = unindexed_vertices(&vertices, &indices);
+        let xs: Vec<f32> = unindexed.iter().map(|vertex| vertex.position[0]).collect();
+        assert_eq!(xs, vec![0.0, 1.0, 2.0, 2.0, 3.0, 0.0]);
+    }
+}
@@ -3,7 +3,7 @@
 //! Provides a high-level interface for managing multiple renderable objects
 
 use std::collections::HashMap;
-use crate::renderable::{Renderable, Triangle, Quad, Cube, Circle, Cylinder, Cone, Sphere};
+use crate::renderable::{Material, Renderable, Triangle, Quad, Cube, Circle, Cylinder, Cone, Sphere};
 
 pub type EntityId = u32;
 
@@ -35,56 +35,63 @@ impl Scene {
     }
     
     /// Add a triangle to the scene and return its ID
-    pub fn add_triangle(&mut self, triangle: Triangle) -> EntityId {
+    pub fn add_triangle(&mut self, mut triangle: Triangle) -> EntityId {
         let id = self.next_id;
+        triangle.set_entity_id(Some(id));
         self.triangles.insert(id, triangle);
         self.next_id += 1;
         id
     }
     
     /// Add a quad to the scene and return its ID
-    pub fn add_quad(&mut self, quad: Quad) -> EntityId {
+    pub fn add_quad(&mut self, mut quad: Quad) -> EntityId {
         let id = self.next_id;
+        quad.set_entity_id(Some(id));
         self.quads.insert(id, quad);
         self.next_id += 1;
         id
     }
     
     /// Add a cube to the scene and return its ID
-    pub fn add_cube(&mut self, cube: Cube) -> EntityId {
+    pub fn add_cube(&mut self, mut cube: Cube) -> EntityId {
         let id = self.next_id;
+        cube.set_entity_id(Some(id));
         self.cubes.insert(id, cube);
         self.next_id += 1;
         id
     }
     
     /// Add a circle to the scene and return its ID
-    pub fn add_circle(&mut self, circle: Circle) -> EntityId {
+    pub fn add_circle(&mut self, mut circle: Circle) -> EntityId {
         let id = self.next_id;
+        circle.set_entity_id(Some(id));
         self.circles.insert(id, circle);
         self.next_id += 1;
         id
     }
     
     /// Add a cylinder to the scene and return its ID
-    pub fn add_cylinder(&mut self, cylinder: Cylinder) -> EntityId {
+    pub fn add_cylinder(&mut self, mut cylinder: Cylinder) -> EntityId {
         let id = self.next_id;
+        cylinder.set_entity_id(Some(id));
         self.cylinders.insert(id, cylinder);
         self.next_id += 1;
         id
     }
     
     /// Add a cone to the scene and return its ID
-    pub fn add_cone(&mut self, cone: Cone) -> EntityId {
+    pub fn add_cone(&mut self, mut cone: Cone) -> EntityId {
         let id = self.next_id;
+        cone.set_entity_id(Some(id));
         self.cones.insert(id, cone);
         self.next_id += 1;
         id
     }
     
     /// Add a sphere to the scene and return its ID
-    pub fn add_sphere(&mut self, sphere: Sphere) -> EntityId {
+    pub fn add_sphere(&mut self, mut sphere: Sphere) -> EntityId {
         let id = self.next_id;
+        sphere.set_entity_id(Some(id));
         self.spheres.insert(id, sphere);
         self.next_id += 1;
         id
@@ -100,27 +107,65 @@ impl Scene {
         self.triangles.get_mut(&id)
     }
     
+    /// Get the material of any entity
+    pub fn get_material(&self, id: EntityId) -> Option<Material> {
+        self.triangles.get(&id).map(|triangle| triangle.get_material())
+            .or_else(|| self.quads.get(&id).map(|quad| quad.get_material()))
+            .or_else(|| self.cubes.get(&id).map(|cube| cube.get_material()))
+            .or_else(|| self.circles.get(&id).map(|circle| circle.get_material()))
+            .or_else(|| self.cylinders.get(&id).map(|cylinder| cylinder.get_material()))
+            .or_else(|| self.cones.get(&id).map(|cone| cone.get_material()))
+            .or_else(|| self.spheres.get(&id).map(|sphere| sphere.get_material()))
+    }
+
+    /// Set the material of any entity, returning false if the entity doesn't exist
+    pub fn set_material(&mut self, id: EntityId, material: Material) -> bool {
+        if let Some(triangle) = self.triangles.get_mut(&id) {
+            triangle.set_material(material);
+        } else if let Some(quad) = self.quads.get_mut(&id) {
+            quad.set_material(material);
+        } else if let Some(cube) = self.cubes.get_mut(&id) {
+            cube.set_material(material);
+        } else if let Some(circle) = self.circles.get_mut(&id) {
+            circle.set_material(material);
+        } else if let Some(cylinder) = self.cylinders.get_mut(&id) {
+            cylinder.set_material(material);
+        } else if let Some(cone) = self.cones.get_mut(&id) {
+            cone.set_material(material);
+        } else if let Some(sphere) = self.spheres.get_mut(&id) {
+            sphere.set_material(material);
+        } else {
+            return false;
+        }
+        true
+    }
+    
     /// Update all entities in the scene
     pub fn update(&mut self, delta_time: f32) {
-        for triangle in self.triangles.values_mut() {
+        self.update_except(delta_time, &[]);
+    }
+
+    /// Update every entity except the given ones, which keep their current transforms
+    pub fn update_except(&mut self, delta_time: f32, skipped: &[EntityId]) {
+        for (_, triangle) in self.triangles.iter_mut().filter(|(id, _)| !skipped.contains(*id)) {
             triangle.update(delta_time);
         }
-        for quad in self.quads.values_mut() {
+        for (_, quad) in self.quads.iter_mut().filter(|(id, _)| !skipped.contains(*id)) {
             quad.update(delta_time);
         }
-        for cube in self.cubes.values_mut() {
+        for (_, cube) in self.cubes.iter_mut().filter(|(id, _)| !skipped.contains(*id)) {
             cube.update(delta_time);
         }
-        for circle in self.circles.values_mut() {
+        for (_, circle) in self.circles.iter_mut().filter(|(id, _)| !skipped.contains(*id)) {
             circle.update(delta_time);
         }
-        for cylinder in self.cylinders.values_mut() {
+        for (_, cylinder) in self.cylinders.iter_mut().filter(|(id, _)| !skipped.contains(*id)) {
             cylinder.update(delta_time);
         }
-        for cone in self.cones.values_mut() {
+        for (_, cone) in self.cones.iter_mut().filter(|(id, _)| !skipped.contains(*id)) {
             cone.update(delta_time);
         }
-        for sphere in self.spheres.values_mut() {
+        for (_, sphere) in self.spheres.iter_mut().filter(|(id, _)| !skipped.contains(*id)) {
             sphere.update(delta_time);
         }
     }
@@ -370,6 +415,33 @@ mod tests {
         assert_eq!(cube_id, 2);
     }
 
+    #[test]
+    fn test_added_objects_carry_their_entity_id() {
+        let mut scene = Scene::new();
+        let quad_id = scene.add_quad(Quad::with_size(1.0, 1.0));
+        let sphere_id = scene.add_sphere(Sphere::new(1.0, 8, 16));
+        assert_eq!(Sphere::new(1.0, 8, 16).entity_id(), None);
+
+        let (_, quads, _, _, _, _, spheres) = scene.get_all_renderables();
+        assert_eq!(quads[0].entity_id(), Some(quad_id));
+        assert_eq!(spheres[0].entity_id(), Some(sphere_id));
+    }
+
+    #[test]
+    fn test_set_material_by_entity() {
+        let mut scene = Scene::new();
+        let cube_id = scene.create_cube(1.0);
+        let sphere_id = scene.add_sphere(Sphere::new(1.0, 8, 16));
+        let red = Material::new([1.0, 0.0, 0.0]);
+
+        assert!(scene.set_material(sphere_id, red));
+        assert_eq!(scene.get_material(sphere_id), Some(red));
+        assert_eq!(scene.get_material(cube_id), Some(Material::default()));
+
+        assert!(!scene.set_material(99, red));
+        assert_eq!(scene.get_material(99), None);
+    }
+
     #[test]
     fn test_create_triangle_methods() {
         let mut scene = Scene::new();
@@ -432,4 +504,21 @@ mod tests {
         // This should not panic - validates that update() can be called
         scene.update(0.016); // 60 FPS delta time
     }
+
+    #[test]
+    fn test_update_except_leaves_skipped_entities_alone() {
+        let mut scene = Scene::new();
+        let ground = scene.add_quad(Quad::with_size(1.0, 1.0));
+        scene.add_cube(Cube::with_size(1.0));
+        let matrices = |scene: &Scene| {
+            let (_, quads, cubes, ..) = scene.get_all_renderables();
+            (quads[0].get_matrix(), cubes[0].get_matrix())
+        };
+        let (ground_before, cube_before) = matrices(&scene);
+
+        scene.update_except(0.5, &[ground]);
+        let (ground_after, cube_after) = matrices(&scene);
+        assert_eq!(ground_after, ground_before);
+        assert_ne!(cube_after, cube_before);
+    }
 }
@@ -0,0 +1,28 @@
+struct Gradient {
+    top: vec4<f32>,
+    bottom: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 0 at the bottom of the frame, 1 at the top
+    @location(0) height: f32,
+}
+
+@group(0) @binding(0) var<uniform> gradient: Gradient;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // Fullscreen triangle: (-1, -1), (3, -1), (-1, 3)
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+    out.height = uv.y;
+    return out;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return mix(gradient.bottom, gradient.top, clamp(input.height, 0.0, 1.0));
+}
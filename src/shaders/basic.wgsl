@@ -1,25 +1,188 @@
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) uv: vec2<f32>,
+}
+
+// Model matrix columns and material, one set per instance
+struct InstanceInput {
+    @location(8) model_0: vec4<f32>,
+    @location(9) model_1: vec4<f32>,
+    @location(10) model_2: vec4<f32>,
+    @location(11) model_3: vec4<f32>,
+    // rgb = base color, a = opacity
+    @location(12) base_color: vec4<f32>,
+    // rgb = emissive, a = roughness
+    @location(13) emissive: vec4<f32>,
+    // x = 1 when multiplying by vertex colors, y = 1 when receiving shadows
+    @location(14) flags: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) world_normal: vec3<f32>,
+    @location(3) emissive: vec3<f32>,
+    @location(4) roughness: f32,
+    @location(5) uv: vec2<f32>,
+    @location(6) receives_shadows: f32,
+}
+
+const MAX_POINT_LIGHTS: u32 = 8u;
+
+struct PointLight {
+    // xyz = position, w = range
+    position_range: vec4<f32>,
+    color: vec4<f32>,
+}
+
+// Colors are premultiplied by their intensity
+struct Lights {
+    ambient: vec4<f32>,
+    // xyz = direction the light travels, w = 1 when enabled
+    directional_direction: vec4<f32>,
+    directional_color: vec4<f32>,
+    camera_position: vec4<f32>,
+    // x = specular strength, y = shininess
+    specular: vec4<f32>,
+    point_light_count: u32,
+    // Seconds since the renderer started
+    time: f32,
+    point_lights: array<PointLight, MAX_POINT_LIGHTS>,
+    // Maps world positions into the directional light's shadow map
+    shadow_view_proj: mat4x4<f32>,
+    // x = 1 when shadows are enabled, y = depth bias, z = texel size, w = PCF radius
+    shadow: vec4<f32>,
 }
 
-@group(0) @binding(0) var<uniform> transform: mat4x4<f32>;
+@group(0) @binding(0) var<uniform> view_proj: mat4x4<f32>;
+@group(1) @binding(0) var<uniform> lights: Lights;
+@group(1) @binding(1) var shadow_map: texture_depth_2d;
+@group(1) @binding(2) var shadow_sampler: sampler_comparison;
+
+// White when the object has no texture
+@group(2) @binding(0) var base_texture: texture_2d<f32>;
+@group(2) @binding(1) var base_sampler: sampler;
 
 @vertex
-fn vs_main(input: VertexInput) -> VertexOutput {
+fn vs_main(input: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+
+    // Normals transform by the inverse transpose of the model matrix, which is the
+    // cofactor matrix up to scale, so non-uniform scaling keeps them perpendicular.
+    // Mirroring flips the cofactor normal along with the winding, and the fragment
+    // shader's front-facing check flips it back.
+    let m = mat3x3<f32>(model[0].xyz, model[1].xyz, model[2].xyz);
+    let cofactor = mat3x3<f32>(cross(m[1], m[2]), cross(m[2], m[0]), cross(m[0], m[1]));
+
+    let world_position = model * vec4<f32>(input.position, 1.0);
+
     var out: VertexOutput;
-    // Apply transform matrix to position
-    out.clip_position = transform * vec4<f32>(input.position, 1.0);
-    out.color = input.color;
+    // Apply the instance's model matrix, then the camera's view-projection
+    out.clip_position = view_proj * world_position;
+    let vertex_color = select(vec3<f32>(1.0), input.color, instance.flags.x > 0.5);
+    out.color = vec4<f32>(instance.base_color.rgb * vertex_color, instance.base_color.a);
+    out.world_position = world_position.xyz;
+    out.world_normal = cofactor * input.normal;
+    out.emissive = instance.emissive.rgb;
+    out.roughness = instance.emissive.a;
+    out.uv = input.uv;
+    out.receives_shadows = instance.flags.y;
     return out;
 }
 
+struct Surface {
+    normal: vec3<f32>,
+    to_camera: vec3<f32>,
+    base_color: vec3<f32>,
+    // The lights' specular settings apply as-is at the default roughness of 0.5
+    specular_strength: f32,
+    shininess: f32,
+}
+
+// Lambert diffuse plus Blinn-Phong specular for one light
+fn shade(surface: Surface, to_light: vec3<f32>, light_color: vec3<f32>) -> vec3<f32> {
+    let diffuse = max(dot(surface.normal, to_light), 0.0);
+    if (diffuse <= 0.0) {
+        return vec3<f32>(0.0);
+    }
+
+    let half_vector = normalize(to_light + surface.to_camera);
+    let specular = pow(max(dot(surface.normal, half_vector), 0.0), surface.shininess) * surface.specular_strength;
+    return light_color * (surface.base_color * diffuse + vec3<f32>(specular));
+}
+
+const MAX_PCF_RADIUS: i32 = 4;
+
+// Fraction of the directional light reaching a point, from 0 in full shadow to 1 fully lit
+fn directional_visibility(world_position: vec3<f32>) -> f32 {
+    let light_clip = lights.shadow_view_proj * vec4<f32>(world_position, 1.0);
+    let light_ndc = light_clip.xyz / light_clip.w;
+    let uv = vec2<f32>(light_ndc.x * 0.5 + 0.5, 0.5 - light_ndc.y * 0.5);
+    let depth = light_ndc.z - lights.shadow.y;
+
+    // Nothing outside the shadow map casts shadows
+    if (any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || depth > 1.0) {
+        return 1.0;
+    }
+
+    // Average comparisons over a square of texels around the lookup to soften the edge
+    let radius = min(i32(lights.shadow.w), MAX_PCF_RADIUS);
+    var lit = 0.0;
+    for (var y = -radius; y <= radius; y++) {
+        for (var x = -radius; x <= radius; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * lights.shadow.z;
+            lit += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, depth);
+        }
+    }
+    let width = f32(2 * radius + 1);
+    return lit / (width * width);
+}
+
 @fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(input.color, 1.0);
+fn fs_main(input: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    let base_color = input.color * textureSample(base_texture, base_sampler, input.uv);
+
+    // Back faces are only visible when culling is off, so light them from their own side
+    var normal = normalize(input.world_normal);
+    if (!front_facing) {
+        normal = -normal;
+    }
+    let smoothness = 2.0 * (1.0 - input.roughness);
+    var surface: Surface;
+    surface.normal = normal;
+    surface.to_camera = normalize(lights.camera_position.xyz - input.world_position);
+    surface.base_color = base_color.rgb;
+    surface.specular_strength = lights.specular.x * smoothness;
+    surface.shininess = max(lights.specular.y * smoothness, 1.0);
+
+    var color = lights.ambient.rgb * surface.base_color + input.emissive;
+
+    if (lights.directional_direction.w > 0.0) {
+        let to_light = -lights.directional_direction.xyz;
+        var visibility = 1.0;
+        if (lights.shadow.x > 0.0 && input.receives_shadows > 0.5) {
+            visibility = directional_visibility(input.world_position);
+        }
+        color += shade(surface, to_light, lights.directional_color.rgb * visibility);
+    }
+
+    for (var i = 0u; i < min(lights.point_light_count, MAX_POINT_LIGHTS); i++) {
+        let light = lights.point_lights[i];
+        let offset = light.position_range.xyz - input.world_position;
+        let distance = length(offset);
+        let range = light.position_range.w;
+        if (distance >= range) {
+            continue;
+        }
+
+        // Smooth falloff that reaches zero at the light's range
+        let falloff = 1.0 - distance / range;
+        let attenuation = falloff * falloff;
+        color += shade(surface, offset / max(distance, 1e-4), light.color.rgb * attenuation);
+    }
+
+    return vec4<f32>(color, base_color.a);
 }
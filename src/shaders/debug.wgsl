@@ -0,0 +1,115 @@
+// Debug views drawn in place of the materials' shaders
+//
+// The view is picked by a `DEBUG_VIEW` constant declared ahead of this source:
+// 0 = unlit base color, 1 = unlit base color on triangle edges only,
+// 2 = depth, 3 = normals, 4 = UVs, 5 = per-object colors.
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) uv: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(8) model_0: vec4<f32>,
+    @location(9) model_1: vec4<f32>,
+    @location(10) model_2: vec4<f32>,
+    @location(11) model_3: vec4<f32>,
+    @location(12) base_color: vec4<f32>,
+    @location(14) flags: vec4<f32>,
+    @location(15) pick_id: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) world_normal: vec3<f32>,
+    @location(3) uv: vec2<f32>,
+    // 1 at the triangle's own corner, 0 at the other two
+    @location(4) barycentric: vec3<f32>,
+    @location(5) @interpolate(flat) pick_id: u32,
+}
+
+// Only the leading fields of the light block are needed
+struct Lights {
+    ambient: vec4<f32>,
+    directional_direction: vec4<f32>,
+    directional_color: vec4<f32>,
+    camera_position: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> view_proj: mat4x4<f32>;
+@group(1) @binding(0) var<uniform> lights: Lights;
+
+// Distance from the camera shown as mid gray in the depth view
+const DEPTH_VIEW_MIDPOINT: f32 = 5.0;
+// Width of the drawn edges in the barycentric wireframe, in pixels
+const EDGE_WIDTH: f32 = 1.0;
+
+@vertex
+fn vs_main(input: VertexInput, instance: InstanceInput, @builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    let m = mat3x3<f32>(model[0].xyz, model[1].xyz, model[2].xyz);
+    let cofactor = mat3x3<f32>(cross(m[1], m[2]), cross(m[2], m[0]), cross(m[0], m[1]));
+
+    let world_position = model * vec4<f32>(input.position, 1.0);
+    let vertex_color = select(vec3<f32>(1.0), input.color, instance.flags.x > 0.5);
+
+    var out: VertexOutput;
+    out.clip_position = view_proj * world_position;
+    out.color = vec4<f32>(instance.base_color.rgb * vertex_color, instance.base_color.a);
+    out.world_position = world_position.xyz;
+    out.world_normal = cofactor * input.normal;
+    out.uv = input.uv;
+    // Only meaningful for unindexed draws, where every three vertices are one triangle
+    let corner = vertex_index % 3u;
+    out.barycentric = vec3<f32>(f32(corner == 0u), f32(corner == 1u), f32(corner == 2u));
+    out.pick_id = instance.pick_id;
+    return out;
+}
+
+// Spread ids over the hue wheel so neighbouring ids get clearly different colors
+fn id_color(pick_id: u32) -> vec3<f32> {
+    if (pick_id == 0u) {
+        return vec3<f32>(0.5);
+    }
+    let hue = fract(f32(pick_id) * 0.618034);
+    let k = fract(vec3<f32>(hue) + vec3<f32>(0.0, 2.0 / 3.0, 1.0 / 3.0));
+    return clamp(abs(k * 6.0 - 3.0) - 1.0, vec3<f32>(0.0), vec3<f32>(1.0)) * 0.8 + 0.2;
+}
+
+@fragment
+fn fs_main(input: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    switch DEBUG_VIEW {
+        case 1u: {
+            // Keep fragments within a pixel-constant width of an edge
+            let distance_in_pixels = input.barycentric / fwidth(input.barycentric);
+            if (min(min(distance_in_pixels.x, distance_in_pixels.y), distance_in_pixels.z) > EDGE_WIDTH) {
+                discard;
+            }
+            return input.color;
+        }
+        case 2u: {
+            let distance = length(input.world_position - lights.camera_position.xyz);
+            return vec4<f32>(vec3<f32>(distance / (distance + DEPTH_VIEW_MIDPOINT)), 1.0);
+        }
+        case 3u: {
+            var normal = normalize(input.world_normal);
+            if (!front_facing) {
+                normal = -normal;
+            }
+            return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+        }
+        case 4u: {
+            return vec4<f32>(fract(input.uv), 0.0, 1.0);
+        }
+        case 5u: {
+            return vec4<f32>(id_color(input.pick_id), 1.0);
+        }
+        default: {
+            return input.color;
+        }
+    }
+}
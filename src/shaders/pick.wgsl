@@ -0,0 +1,40 @@
+// Draws each object's picking id into the ID buffer, with its depth alongside
+
+struct InstanceInput {
+    @location(8) model_0: vec4<f32>,
+    @location(9) model_1: vec4<f32>,
+    @location(10) model_2: vec4<f32>,
+    @location(11) model_3: vec4<f32>,
+    // Entity id plus one, 0 for objects without an id
+    @location(15) pick_id: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) pick_id: u32,
+}
+
+@group(0) @binding(0) var<uniform> view_proj: mat4x4<f32>;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    var out: VertexOutput;
+    out.clip_position = view_proj * model * vec4<f32>(position, 1.0);
+    out.pick_id = instance.pick_id;
+    return out;
+}
+
+struct PickOutput {
+    @location(0) pick_id: u32,
+    // The depth's bits, since not every backend can copy depth textures or render float ones
+    @location(1) depth_bits: u32,
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> PickOutput {
+    var out: PickOutput;
+    out.pick_id = in.pick_id;
+    out.depth_bits = bitcast<u32>(in.clip_position.z);
+    return out;
+}
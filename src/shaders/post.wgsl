@@ -0,0 +1,144 @@
+// Fullscreen post-processing passes
+//
+// Every pass reads the previous pass's output from `source` and writes one
+// color per pixel. Colors are linear; sRGB targets encode them on write.
+
+struct PassSettings {
+    // Meaning depends on the pass, see each entry point
+    params: vec4<f32>,
+    // 1 / source size in pixels
+    texel_size: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 0,0 at the top left of the frame, 1,1 at the bottom right
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: PassSettings;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // Fullscreen triangle: (-1, -1), (3, -1), (-1, 3)
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+fn sample_source(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(source, source_sampler, uv, 0.0);
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Luma for edge detection, roughly perceptual so dark edges are found too
+fn fxaa_luma(color: vec3<f32>) -> f32 {
+    return sqrt(luminance(color));
+}
+
+const FXAA_REDUCE_MIN: f32 = 1.0 / 128.0;
+const FXAA_REDUCE_MUL: f32 = 1.0 / 8.0;
+const FXAA_SPAN_MAX: f32 = 8.0;
+
+@fragment
+fn fs_fxaa(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = settings.texel_size;
+    let center = sample_source(input.uv);
+    let luma_nw = fxaa_luma(sample_source(input.uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = fxaa_luma(sample_source(input.uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = fxaa_luma(sample_source(input.uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = fxaa_luma(sample_source(input.uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_m = fxaa_luma(center.rgb);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Blur along the edge, perpendicular to the luma gradient
+    var direction = vec2<f32>(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-FXAA_SPAN_MAX), vec2<f32>(FXAA_SPAN_MAX)) * texel;
+
+    let near = 0.5 * (
+        sample_source(input.uv + direction * (1.0 / 3.0 - 0.5)) +
+        sample_source(input.uv + direction * (2.0 / 3.0 - 0.5))
+    );
+    let far = near * 0.5 + 0.25 * (
+        sample_source(input.uv - direction * 0.5) +
+        sample_source(input.uv + direction * 0.5)
+    );
+
+    // The wider sample overshot the edge if it left the local luma range
+    let luma_far = fxaa_luma(far.rgb);
+    let color = select(far.rgb, near.rgb, luma_far < luma_min || luma_far > luma_max);
+    return vec4<f32>(color, center.a);
+}
+
+// Narkowicz's fit of the ACES filmic curve
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// params: x = exposure, y = 0 for Reinhard or 1 for ACES
+@fragment
+fn fs_tonemap(input: VertexOutput) -> @location(0) vec4<f32> {
+    let source_color = sample_source(input.uv);
+    let exposed = max(source_color.rgb * settings.params.x, vec3<f32>(0.0));
+    let reinhard = exposed / (1.0 + exposed);
+    let color = select(reinhard, aces(exposed), settings.params.y > 0.5);
+    return vec4<f32>(color, source_color.a);
+}
+
+// params: x = strength, y = radius where darkening starts
+@fragment
+fn fs_vignette(input: VertexOutput) -> @location(0) vec4<f32> {
+    let source_color = sample_source(input.uv);
+    // 0 at the center, 1 in the corners
+    let corner_distance = length(input.uv - 0.5) * sqrt(2.0);
+    let darkening = settings.params.x * smoothstep(min(settings.params.y, 0.999), 1.0, corner_distance);
+    return vec4<f32>(source_color.rgb * (1.0 - darkening), source_color.a);
+}
+
+// params: x = amount
+@fragment
+fn fs_grayscale(input: VertexOutput) -> @location(0) vec4<f32> {
+    let source_color = sample_source(input.uv);
+    let gray = vec3<f32>(luminance(source_color.rgb));
+    return vec4<f32>(mix(source_color.rgb, gray, settings.params.x), source_color.a);
+}
+
+const MAX_BLUR_RADIUS: i32 = 32;
+
+// One direction of a separable gaussian blur
+// params: x = sigma in pixels, yz = direction (1, 0 or 0, 1)
+@fragment
+fn fs_blur(input: VertexOutput) -> @location(0) vec4<f32> {
+    let sigma = max(settings.params.x, 1e-3);
+    let texel_step = settings.params.yz * settings.texel_size;
+    let radius = min(i32(ceil(3.0 * sigma)), MAX_BLUR_RADIUS);
+
+    var total = vec4<f32>(0.0);
+    var total_weight = 0.0;
+    for (var i = -radius; i <= radius; i++) {
+        let offset = f32(i);
+        let weight = exp(-(offset * offset) / (2.0 * sigma * sigma));
+        total += sample_source(input.uv + texel_step * offset) * weight;
+        total_weight += weight;
+    }
+    return total / total_weight;
+}
@@ -0,0 +1,16 @@
+// Depth-only pass drawing shadow casters from the directional light's point of view
+
+struct InstanceInput {
+    @location(8) model_0: vec4<f32>,
+    @location(9) model_1: vec4<f32>,
+    @location(10) model_2: vec4<f32>,
+    @location(11) model_3: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> light_view_proj: mat4x4<f32>;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    return light_view_proj * model * vec4<f32>(position, 1.0);
+}
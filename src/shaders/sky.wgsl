@@ -0,0 +1,72 @@
+// Skies drawn behind every object, looked up by view direction
+
+struct Sky {
+    // Camera basis in world space; the pixel at normalized device coordinates
+    // (x, y) looks along forward + x * right + y * up
+    right: vec4<f32>,
+    up: vec4<f32>,
+    forward: vec4<f32>,
+    // Procedural sky colors
+    zenith: vec4<f32>,
+    horizon: vec4<f32>,
+    ground: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+const PI: f32 = 3.14159265358979;
+
+@group(0) @binding(0) var<uniform> sky: Sky;
+
+// Only the binding matching the environment's kind is used by each pipeline
+@group(1) @binding(0) var environment_cube: texture_cube<f32>;
+@group(1) @binding(1) var environment_panorama: texture_2d<f32>;
+@group(1) @binding(2) var environment_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // Fullscreen triangle: (-1, -1), (3, -1), (-1, 3)
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let ndc = uv * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    // On the far plane, so the sky only covers pixels no object has drawn to
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+fn view_direction(ndc: vec2<f32>) -> vec3<f32> {
+    return normalize(sky.forward.xyz + ndc.x * sky.right.xyz + ndc.y * sky.up.xyz);
+}
+
+@fragment
+fn fs_sky(input: VertexOutput) -> @location(0) vec4<f32> {
+    let height = view_direction(input.ndc).y;
+    // Square root keeps the horizon band narrow, like a real sky
+    let blend = sqrt(abs(height));
+    return select(
+        mix(sky.horizon, sky.ground, blend),
+        mix(sky.horizon, sky.zenith, blend),
+        height >= 0.0,
+    );
+}
+
+@fragment
+fn fs_cubemap(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSampleLevel(environment_cube, environment_sampler, view_direction(input.ndc), 0.0);
+}
+
+// Equirectangular: longitude across the width, the zenith at the top row
+@fragment
+fn fs_panorama(input: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = view_direction(input.ndc);
+    let uv = vec2<f32>(
+        0.5 + atan2(direction.z, direction.x) / (2.0 * PI),
+        acos(clamp(direction.y, -1.0, 1.0)) / PI,
+    );
+    return textureSampleLevel(environment_panorama, environment_sampler, uv, 0.0);
+}
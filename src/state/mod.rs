@@ -1,21 +1,32 @@
 use web_time::Instant;
 use winit::window::Window;
 
-use crate::renderer::{Renderer, camera::ProjectionMode};
+use crate::renderer::{Renderer, ShadowSettings, camera::ProjectionMode};
 use crate::renderable::Renderable;
-use crate::scene::Scene;
+use crate::scene::{EntityId, Scene};
 
 pub struct State {
     pub window: std::sync::Arc<Window>,
     pub renderer: Renderer,
     pub scene: Scene,
     pub projection_mode: ProjectionMode,
+    /// Ground plane, left out of the scene's animation
+    ground: EntityId,
     last_frame_time: Instant,
 }
 
 impl State {
     pub async fn new(window: std::sync::Arc<Window>) -> Result<State, Box<dyn std::error::Error>> {
-        let renderer = Renderer::new(window.clone()).await?;
+        let mut renderer = Renderer::new(window.clone()).await?;
+
+        // Development builds can edit shaders live by pointing MAP_SHADER_DIR at a directory of .wgsl files
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        if let Ok(directory) = std::env::var("MAP_SHADER_DIR") {
+            match renderer.watch_shaders(&directory) {
+                Ok(()) => log::info!("Watching {} for shader changes", directory),
+                Err(error) => log::warn!("Can't watch shader directory {}: {}", directory, error),
+            }
+        }
         let mut scene = Scene::new();
 
         // Create three triangles using Scene primitive functions - spread across depth
@@ -63,7 +74,20 @@ impl State {
         let mut sphere = crate::renderable::Sphere::new(0.25, 20, 20);
         sphere.transform_set_position(glam::Vec3::new(1.0, 2.0, -2.5));
         let id10 = scene.add_sphere(sphere);
-        log::info!("Created sphere at (1.0, 2.0, -2.5) with ID {}", id10);        log::info!(
+        log::info!("Created sphere at (1.0, 2.0, -2.5) with ID {}", id10);
+
+        // Static ground plane below everything, so the shadows show where each object sits
+        let mut ground = crate::renderable::Quad::with_size(14.0, 14.0);
+        ground.transform_set_position(glam::Vec3::new(0.0, -1.5, -4.5));
+        ground.transform_rotate_degrees(-90.0, 0.0, 0.0);
+        ground.set_material(crate::renderable::Material::new([0.6, 0.6, 0.6]).with_cast_shadows(false));
+        let ground = scene.add_quad(ground);
+        log::info!("Created ground at (0.0, -1.5, -4.5) with ID {}", ground);
+
+        if let Some(light) = &mut renderer.lighting_mut().directional {
+            light.shadows = Some(ShadowSettings::default());
+        }
+        log::info!(
             "Total objects in scene: {} triangles, {} quads, {} cubes, {} circles, {} cylinders, {} cones, {} spheres",
             scene.triangle_count(),
             scene.quad_count(),
@@ -79,6 +103,7 @@ impl State {
             renderer,
             scene,
             projection_mode: ProjectionMode::Perspective,
+            ground,
             last_frame_time: Instant::now(),
         })
     }
@@ -107,8 +132,8 @@ impl State {
         // Cap delta time to avoid huge jumps (e.g., when debugging or app is backgrounded)
         let delta_time = delta_time.min(0.1); // Max 100ms per frame
 
-        // Update the scene with real delta time
-        self.scene.update(delta_time);
+        // Update the scene with real delta time, keeping the ground still
+        self.scene.update_except(delta_time, &[self.ground]);
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {